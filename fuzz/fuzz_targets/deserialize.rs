@@ -0,0 +1,12 @@
+//! Feeds arbitrary bytes to the deserializer of [Nftables].
+//!
+//! Run with `cargo +nightly fuzz run deserialize`. Invalid input must be
+//! rejected with an error; any panic is reported as a crash.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nftables::schema::Nftables;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Nftables>(data);
+});
@@ -1,6 +1,20 @@
+use std::collections::HashMap;
+use std::mem::Discriminant;
+
 use serde::{Deserialize, Serialize};
 
-use crate::schema::{NfCmd, NfListObject, NfObject, Nftables};
+use crate::explain;
+use crate::expr::{
+    Expression, Meta, MetaKey, NamedExpression, Payload, PayloadProtocol, SetItem, Verdict,
+};
+use crate::helper::{self, NftablesError};
+use crate::schema::{
+    CTHelper, Chain, Element, FlushObject, Map, NfCmd, NfListObject, NfObject, Nftables, Rule, Set,
+    Table,
+};
+use crate::stmt::{Match, Operator, Reject, RejectType, Statement};
+use crate::types::NfFamily;
+use crate::validation::ReferenceWarning;
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// Batch manages nftables objects and is used to prepare an nftables payload.
@@ -30,11 +44,36 @@ impl Batch {
         self.data.push(NfObject::CmdObject(NfCmd::Delete(obj)))
     }
 
+    /// Adds `rule` with `insert` command to Batch, positioned before the
+    /// rule at the given zero-based `index` of its chain.
+    ///
+    /// Any handle of `rule` is cleared, as index and handle are alternatives.
+    pub fn insert_at_index(&mut self, mut rule: Rule, index: u32) {
+        rule.handle = None;
+        rule.index = Some(index);
+        self.add_cmd(NfCmd::Insert(NfListObject::Rule(rule)))
+    }
+
+    /// Adds `rule` with `insert` command to Batch, positioned before the
+    /// rule with the given `handle`.
+    ///
+    /// Any index of `rule` is cleared, as index and handle are alternatives.
+    pub fn insert_before_handle(&mut self, mut rule: Rule, handle: u32) {
+        rule.index = None;
+        rule.handle = Some(handle);
+        self.add_cmd(NfCmd::Insert(NfListObject::Rule(rule)))
+    }
+
     /// Adds a command to Batch.
     pub fn add_cmd(&mut self, cmd: NfCmd) {
         self.data.push(NfObject::CmdObject(cmd))
     }
 
+    /// Adds a `flush ruleset` command to Batch, removing all tables from the live ruleset.
+    pub fn flush_ruleset(&mut self) {
+        self.add_cmd(NfCmd::Flush(FlushObject::Ruleset(None)))
+    }
+
     /// Adds a list object (without a command) directly to Batch.
     /// This corresponds to the descriptive output format of `nft -j list ruleset`.
     pub fn add_obj(&mut self, obj: NfListObject) {
@@ -46,8 +85,515 @@ impl Batch {
         self.data.extend(objs)
     }
 
+    /// Removes all objects that are identical to an earlier object in Batch,
+    /// keeping the first occurrence in place.
+    ///
+    /// Several schema types contain [HashSet](std::collections::HashSet)s and
+    /// cannot be hashed, so objects are looked up by their JSON form with
+    /// sorted flags and only compared with [PartialEq] if those are equal.
+    pub fn dedup(&mut self) {
+        let mut unique: Vec<NfObject> = Vec::with_capacity(self.data.len());
+        let mut seen: HashMap<String, Vec<usize>> = HashMap::with_capacity(self.data.len());
+        for obj in self.data.drain(..) {
+            let mut json = serde_json::to_value(&obj).expect("failed to serialize NfObject");
+            helper::sort_flags(&mut json);
+            let candidates = seen.entry(json.to_string()).or_default();
+            if !candidates.iter().any(|&index| unique[index] == obj) {
+                candidates.push(unique.len());
+                unique.push(obj);
+            }
+        }
+        self.data = unique;
+    }
+
+    /// Adds set elements with `add` commands to Batch, using one command
+    /// per `chunk_size` elements.
+    ///
+    /// This keeps single commands within netlink message size limits when
+    /// adding large numbers of elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn add_elements_chunked(&mut self, element: Element, chunk_size: usize) {
+        for chunk in Self::chunk_elements(element, chunk_size) {
+            self.add(NfListObject::Element(chunk))
+        }
+    }
+
+    /// Adds set elements with `delete` commands to Batch, using one command
+    /// per `chunk_size` elements (see [Batch::add_elements_chunked]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn delete_elements_chunked(&mut self, element: Element, chunk_size: usize) {
+        for chunk in Self::chunk_elements(element, chunk_size) {
+            self.delete(NfListObject::Element(chunk))
+        }
+    }
+
+    /// Splits the elements of `element` into chunks of at most `chunk_size` elements.
+    fn chunk_elements(element: Element, chunk_size: usize) -> Vec<Element> {
+        element
+            .elem
+            .chunks(chunk_size)
+            .map(|elem| Element {
+                family: element.family,
+                table: element.table.clone(),
+                name: element.name.clone(),
+                elem: elem.to_vec(),
+            })
+            .collect()
+    }
+
+    /// Adds objects to the given table through a [TableScope].
+    ///
+    /// Every object added within `f` gets its `family` and `table` fields
+    /// set to the scope's table. The table itself is not added.
+    pub fn in_table<F>(&mut self, family: NfFamily, table: impl Into<String>, f: F)
+    where
+        F: FnOnce(&mut TableScope),
+    {
+        let mut scope = TableScope {
+            batch: self,
+            family,
+            table: table.into(),
+        };
+        f(&mut scope);
+    }
+
+    /// Adds a table with `add` command to Batch, then adds objects to it
+    /// through a [TableScope] (see [Batch::in_table]).
+    pub fn add_table_with<F>(&mut self, family: NfFamily, name: impl Into<String>, f: F)
+    where
+        F: FnOnce(&mut TableScope),
+    {
+        let name = name.into();
+        self.add(NfListObject::Table(Table {
+            family,
+            name: name.clone(),
+            handle: None,
+        }));
+        self.in_table(family, name, f);
+    }
+
+    /// Checks that objects referenced in Batch are defined in it as well.
+    ///
+    /// Currently, the following references are checked:
+    ///
+    /// * Target chains of `jump` and `goto` verdicts in rules and map elements.
+    /// * The protocol of [ct helpers](crate::schema::CTHelper) assigned in a
+    ///   rule against the protocols matched earlier in that rule (best-effort).
+    /// * `reject with tcp reset` in rules not matching TCP earlier (best-effort).
+    ///
+    /// As the referenced objects may already exist in the live ruleset,
+    /// the returned warnings do not necessarily indicate an error.
+    pub fn validate_references(&self) -> Vec<ReferenceWarning> {
+        let mut warnings = Vec::new();
+        self.check_jump_targets(&mut warnings);
+        self.check_ct_helpers(&mut warnings);
+        self.check_tcp_resets(&mut warnings);
+        warnings
+    }
+
+    /// Warns about `jump` and `goto` targets not defined in Batch.
+    fn check_jump_targets(&self, warnings: &mut Vec<ReferenceWarning>) {
+        let chains: Vec<(NfFamily, &str, &str)> = self
+            .defined_objects()
+            .filter_map(|obj| match obj {
+                NfListObject::Chain(c) => Some((c.family, c.table.as_str(), c.name.as_str())),
+                _ => None,
+            })
+            .collect();
+
+        for obj in self.defined_objects() {
+            let mut targets = Vec::new();
+            let (family, table) = match obj {
+                NfListObject::Rule(rule) => {
+                    for stmt in &rule.expr {
+                        statement_jump_targets(stmt, &mut targets);
+                    }
+                    (rule.family, &rule.table)
+                }
+                NfListObject::Element(elem) => {
+                    for expr in &elem.elem {
+                        expression_jump_targets(expr, &mut targets);
+                    }
+                    (elem.family, &elem.table)
+                }
+                _ => continue,
+            };
+            for target in targets {
+                if chains.contains(&(family, table, target)) {
+                    continue;
+                }
+                push_warning(
+                    warnings,
+                    ReferenceWarning::UndefinedChain {
+                        family,
+                        table: table.clone(),
+                        chain: target.to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Warns about ct helpers assigned in rules matching a different layer 4 protocol.
+    fn check_ct_helpers(&self, warnings: &mut Vec<ReferenceWarning>) {
+        let helpers: Vec<&CTHelper> = self
+            .defined_objects()
+            .filter_map(|obj| match obj {
+                NfListObject::CTHelper(h) => Some(h),
+                _ => None,
+            })
+            .collect();
+
+        for obj in self.defined_objects() {
+            let rule = match obj {
+                NfListObject::Rule(rule) => rule,
+                _ => continue,
+            };
+            let mut matched_protocols = Vec::new();
+            for stmt in &rule.expr {
+                match stmt {
+                    Statement::Match(m) => {
+                        if let Some(protocol) = matched_l4_protocol(m) {
+                            matched_protocols.push(protocol);
+                        }
+                    }
+                    Statement::CTHelper(name) => {
+                        let helper_protocol = helpers
+                            .iter()
+                            .find(|h| {
+                                h.family == rule.family && h.table == rule.table && &h.name == name
+                            })
+                            .and_then(|h| h.protocol.as_deref());
+                        let helper_protocol = match helper_protocol {
+                            Some(p) => p,
+                            None => continue,
+                        };
+                        if let Some(matched) =
+                            matched_protocols.iter().find(|p| **p != helper_protocol)
+                        {
+                            push_warning(
+                                warnings,
+                                ReferenceWarning::CTHelperProtocolMismatch {
+                                    family: rule.family,
+                                    table: rule.table.clone(),
+                                    helper: name.clone(),
+                                    helper_protocol: helper_protocol.to_string(),
+                                    matched_protocol: matched.to_string(),
+                                },
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Warns about `reject with tcp reset` in rules not matching TCP before.
+    fn check_tcp_resets(&self, warnings: &mut Vec<ReferenceWarning>) {
+        for obj in self.defined_objects() {
+            let rule = match obj {
+                NfListObject::Rule(rule) => rule,
+                _ => continue,
+            };
+            let mut matched_protocols = Vec::new();
+            for stmt in &rule.expr {
+                match stmt {
+                    Statement::Match(m) => {
+                        if let Some(protocol) = matched_l4_protocol(m) {
+                            matched_protocols.push(protocol);
+                        }
+                    }
+                    Statement::Reject(Some(Reject {
+                        _type: Some(RejectType::TCPReset),
+                        ..
+                    })) => {
+                        if matched_protocols.contains(&"tcp") {
+                            continue;
+                        }
+                        push_warning(
+                            warnings,
+                            ReferenceWarning::TcpResetWithoutTcp {
+                                family: rule.family,
+                                table: rule.table.clone(),
+                                chain: rule.chain.clone(),
+                                matched_protocol: matched_protocols.first().map(|p| p.to_string()),
+                            },
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Returns the objects added, created, inserted or listed in Batch.
+    fn defined_objects(&self) -> impl Iterator<Item = &NfListObject> {
+        self.data.iter().filter_map(|obj| match obj {
+            NfObject::CmdObject(NfCmd::Add(obj))
+            | NfObject::CmdObject(NfCmd::Create(obj))
+            | NfObject::CmdObject(NfCmd::Insert(obj)) => Some(obj),
+            NfObject::ListObject(obj) => Some(obj.as_ref()),
+            _ => None,
+        })
+    }
+
+    /// Renders the commands in Batch in nft syntax, one line per command,
+    /// without running nft.
+    ///
+    /// The lines are meant for humans, e.g. for a dry-run mode. Statements
+    /// and expressions without known nft syntax are rendered as JSON, so the
+    /// lines are not necessarily valid nft input.
+    pub fn explain(&self) -> Vec<String> {
+        self.data.iter().map(explain::render_object).collect()
+    }
+
+    /// Applies Batch and returns a copy whose objects carry the handles
+    /// assigned by the kernel, e.g. to delete them by handle later on.
+    ///
+    /// Handles are taken from the commands nft echoes (see
+    /// [apply_ruleset_echo](crate::helper::apply_ruleset_echo)) and matched
+    /// to the `add`, `create` and `insert` commands of Batch by kind, family,
+    /// table and name. Rules are matched by their chain and their position
+    /// among the rules of that chain in Batch. Objects without a matching
+    /// echoed object are returned with a handle of `None`.
+    pub fn apply_and_collect_handles(&self, program: Option<&str>) -> Result<Batch, NftablesError> {
+        let nftables = Nftables {
+            objects: self.data.clone(),
+        };
+        let echoed = helper::apply_ruleset_echo(&nftables, program, None)?;
+        let echoed: Vec<&NfListObject> = echoed.objects.iter().filter_map(created_object).collect();
+        let handles: HashMap<EchoKey, u32> = echo_keys(echoed.iter().copied())
+            .into_iter()
+            .zip(&echoed)
+            .filter_map(|(key, obj)| Some((key?, obj.handle()?)))
+            .collect();
+
+        let keys = echo_keys(self.data.iter().filter_map(created_object));
+        let mut batch = self.clone();
+        let created = batch.data.iter_mut().filter_map(|obj| match obj {
+            NfObject::CmdObject(NfCmd::Add(obj))
+            | NfObject::CmdObject(NfCmd::Create(obj))
+            | NfObject::CmdObject(NfCmd::Insert(obj)) => Some(obj),
+            _ => None,
+        });
+        for (obj, key) in created.zip(keys) {
+            obj.set_handle(key.and_then(|key| handles.get(&key).copied()));
+        }
+        Ok(batch)
+    }
+
     /// Wraps Batch in nftables object.
     pub fn to_nftables(self) -> Nftables {
         Nftables { objects: self.data }
     }
 }
+
+/// Identifies an object created by a batch among the objects echoed by nft:
+/// kind, family, table, name (or chain for rules) and the number of earlier
+/// objects with the same identity.
+type EchoKey<'a> = (
+    Discriminant<NfListObject>,
+    NfFamily,
+    &'a str,
+    &'a str,
+    usize,
+);
+
+/// Returns the [EchoKey] of each object, or `None` for objects without handle.
+fn echo_keys<'a>(objects: impl IntoIterator<Item = &'a NfListObject>) -> Vec<Option<EchoKey<'a>>> {
+    let mut seen = HashMap::new();
+    objects
+        .into_iter()
+        .map(|obj| {
+            let (family, table, name) = match obj {
+                NfListObject::Table(o) => (o.family, "", o.name.as_str()),
+                NfListObject::Chain(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::Rule(o) => (o.family, o.table.as_str(), o.chain.as_str()),
+                NfListObject::Set(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::Map(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::FlowTable(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::Counter(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::Quota(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::CTHelper(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::Limit(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::CTTimeout(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::CTExpectation(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::SynProxy(o) => (o.family, o.table.as_str(), o.name.as_str()),
+                NfListObject::Element(_) | NfListObject::MetainfoObject(_) => return None,
+            };
+            let identity = (std::mem::discriminant(obj), family, table, name);
+            let occurrence = seen.entry(identity).or_insert(0);
+            *occurrence += 1;
+            Some((identity.0, family, table, name, *occurrence))
+        })
+        .collect()
+}
+
+/// Returns the object of an echoed command creating or replacing an object.
+fn created_object(obj: &NfObject) -> Option<&NfListObject> {
+    match obj {
+        NfObject::CmdObject(NfCmd::Add(obj))
+        | NfObject::CmdObject(NfCmd::Create(obj))
+        | NfObject::CmdObject(NfCmd::Insert(obj)) => Some(obj),
+        _ => None,
+    }
+}
+
+/// Adds `warning` to `warnings` unless it was reported before.
+fn push_warning(warnings: &mut Vec<ReferenceWarning>, warning: ReferenceWarning) {
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+}
+
+/// Layer 4 protocols that can be matched by a payload expression of the same name.
+const L4_PROTOCOLS: &[&str] = &["tcp", "udp", "udplite", "dccp", "sctp"];
+
+/// Returns the layer 4 protocol a match requires, if evident from the match.
+///
+/// Detects header field matches like `tcp dport 21` as well as explicit
+/// protocol matches like `meta l4proto udp` or `ip protocol udp`.
+fn matched_l4_protocol(m: &Match) -> Option<&str> {
+    if m.op != Operator::EQ {
+        return None;
+    }
+    match &m.left {
+        Expression::Named(NamedExpression::Payload(Payload::PayloadField(field))) => {
+            let explicit = matches!(
+                (&field.protocol, field.field.as_str()),
+                (PayloadProtocol::Ip, "protocol") | (PayloadProtocol::Ip6, "nexthdr")
+            );
+            if explicit {
+                match &m.right {
+                    Expression::String(p) => Some(p.as_str()),
+                    _ => None,
+                }
+            } else {
+                L4_PROTOCOLS
+                    .iter()
+                    .find(|p| PayloadProtocol::from(**p) == field.protocol)
+                    .copied()
+            }
+        }
+        Expression::Named(NamedExpression::Meta(Meta {
+            key: MetaKey::L4proto,
+        })) => match &m.right {
+            Expression::String(p) => Some(p.as_str()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Collects the target chains of `jump` and `goto` verdicts in a statement.
+fn statement_jump_targets<'a>(stmt: &'a Statement, targets: &mut Vec<&'a str>) {
+    match stmt {
+        Statement::Jump(t) | Statement::Goto(t) => targets.push(&t.target),
+        Statement::VerdictMap(vmap) => expression_jump_targets(&vmap.data, targets),
+        _ => {}
+    }
+}
+
+/// Collects the target chains of `jump` and `goto` verdicts in an expression.
+fn expression_jump_targets<'a>(expr: &'a Expression, targets: &mut Vec<&'a str>) {
+    match expr {
+        Expression::Verdict(Verdict::Jump(t)) | Expression::Verdict(Verdict::Goto(t)) => {
+            targets.push(&t.target)
+        }
+        Expression::List(exprs) => exprs
+            .iter()
+            .for_each(|expr| expression_jump_targets(expr, targets)),
+        Expression::Named(NamedExpression::Set(items)) => {
+            for item in items {
+                match item {
+                    SetItem::Element(expr) => expression_jump_targets(expr, targets),
+                    SetItem::Mapping(_, expr) => expression_jump_targets(expr, targets),
+                    SetItem::MappingStatement(_, stmt) => statement_jump_targets(stmt, targets),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Adds objects to a [Batch] on behalf of a single table.
+///
+/// Created by [Batch::in_table] and [Batch::add_table_with].
+pub struct TableScope<'a> {
+    batch: &'a mut Batch,
+    family: NfFamily,
+    table: String,
+}
+
+impl TableScope<'_> {
+    /// Adds object with `add` command to the table.
+    pub fn add(&mut self, obj: NfListObject) {
+        let obj = self.scoped(obj);
+        self.batch.add(obj)
+    }
+
+    /// Adds object with `delete` command to the table.
+    pub fn delete(&mut self, obj: NfListObject) {
+        let obj = self.scoped(obj);
+        self.batch.delete(obj)
+    }
+
+    /// Adds a chain to the table.
+    pub fn chain(&mut self, chain: Chain) {
+        self.add(NfListObject::Chain(chain))
+    }
+
+    /// Adds a rule to the table.
+    pub fn rule(&mut self, rule: Rule) {
+        self.add(NfListObject::Rule(rule))
+    }
+
+    /// Adds a set to the table.
+    pub fn set(&mut self, set: Set) {
+        self.add(NfListObject::Set(set))
+    }
+
+    /// Adds a map to the table.
+    pub fn map(&mut self, map: Map) {
+        self.add(NfListObject::Map(map))
+    }
+
+    /// Adds set elements to the table.
+    pub fn element(&mut self, element: Element) {
+        self.add(NfListObject::Element(element))
+    }
+
+    /// Sets family and table of the given object to the ones of this scope.
+    fn scoped(&self, mut obj: NfListObject) -> NfListObject {
+        let family = self.family;
+        let table = self.table.clone();
+        match &mut obj {
+            NfListObject::Table(o) => {
+                o.family = family;
+                o.name = table;
+            }
+            NfListObject::Chain(o) => (o.family, o.table) = (family, table),
+            NfListObject::Rule(o) => (o.family, o.table) = (family, table),
+            NfListObject::Set(o) => (o.family, o.table) = (family, table),
+            NfListObject::Map(o) => (o.family, o.table) = (family, table),
+            NfListObject::Element(o) => (o.family, o.table) = (family, table),
+            NfListObject::FlowTable(o) => (o.family, o.table) = (family, table),
+            NfListObject::Counter(o) => (o.family, o.table) = (family, table),
+            NfListObject::Quota(o) => (o.family, o.table) = (family, table),
+            NfListObject::CTHelper(o) => (o.family, o.table) = (family, table),
+            NfListObject::Limit(o) => (o.family, o.table) = (family, table),
+            NfListObject::CTTimeout(o) => (o.family, o.table) = (family, table),
+            NfListObject::CTExpectation(o) => (o.family, o.table) = (family, table),
+            NfListObject::SynProxy(o) => (o.family, o.table) = (family, table),
+            NfListObject::MetainfoObject(_) => {}
+        }
+        obj
+    }
+}
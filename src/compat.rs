@@ -0,0 +1,69 @@
+use serde_json::{Map, Value};
+
+use crate::schema::Nftables;
+
+/// Selects whether JSON documents are adapted from older nftables releases before parsing.
+///
+/// Older nftables releases emitted some properties under different names.
+/// The following renames are applied in compatibility mode:
+///
+/// * The [mangle statement](crate::stmt::Mangle) used `left` and `right`
+///   for what is now called `key` and `value`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum CompatMode {
+    /// Parse the document as-is.
+    Off,
+    /// Adapt the document if its [metainfo](crate::schema::MetainfoObject)
+    /// lacks a `json_schema_version`, as the output of older nftables releases does.
+    #[default]
+    Auto,
+    /// Always adapt the document.
+    Legacy,
+}
+
+/// Parses an nftables JSON document, adapting it according to `mode`.
+pub fn from_str(s: &str, mode: CompatMode) -> Result<Nftables, serde_json::Error> {
+    from_value(serde_json::from_str(s)?, mode)
+}
+
+/// Parses an nftables JSON value, adapting it according to `mode`.
+pub fn from_value(mut value: Value, mode: CompatMode) -> Result<Nftables, serde_json::Error> {
+    let legacy = match mode {
+        CompatMode::Off => false,
+        CompatMode::Auto => schema_version(&value).is_none(),
+        CompatMode::Legacy => true,
+    };
+    if legacy {
+        rename_mangle_properties(&mut value);
+    }
+    serde_json::from_value(value)
+}
+
+/// Returns the `json_schema_version` of the document's metainfo object, if any.
+fn schema_version(value: &Value) -> Option<u64> {
+    value["nftables"]
+        .as_array()?
+        .iter()
+        .find_map(|obj| obj["metainfo"]["json_schema_version"].as_u64())
+}
+
+/// Renames `left`/`right` of all mangle statements to `key`/`value`.
+fn rename_mangle_properties(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Object(mangle)) = map.get_mut("mangle") {
+                rename(mangle, "left", "key");
+                rename(mangle, "right", "value");
+            }
+            map.values_mut().for_each(rename_mangle_properties);
+        }
+        Value::Array(values) => values.iter_mut().for_each(rename_mangle_properties),
+        _ => {}
+    }
+}
+
+fn rename(map: &mut Map<String, Value>, from: &str, to: &str) {
+    if let Some(v) = map.remove(from) {
+        map.entry(to).or_insert(v);
+    }
+}
@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+/// An nftables JSON document, or a part of it, could not be parsed.
+///
+/// Parsing does not run nft, so this error is independent of
+/// [NftablesError](crate::helper::NftablesError), which wraps it.
+pub enum ParseError {
+    #[error("got invalid json: {0}")]
+    /// The JSON is malformed or does not describe the expected type.
+    InvalidJson(serde_json::Error),
+    /// A document could not be parsed, with the path to the invalid property.
+    ///
+    /// Returned when parsing an [Nftables](crate::schema::Nftables) document with [str::parse].
+    #[error("got invalid json at {path}: {inner}")]
+    InvalidJsonAt {
+        /// Path to the invalid property, e.g. `nftables[2]`.
+        path: String,
+        inner: serde_json::Error,
+    },
+}
@@ -0,0 +1,335 @@
+use std::net::IpAddr;
+
+use serde_json::{Map, Value};
+
+use crate::schema::NfObject;
+
+/// Renders an object of an nftables document as a line in nft syntax.
+///
+/// Rendering works on the JSON form of the object, so that every object can
+/// be rendered. Statements and expressions without a known nft syntax are
+/// rendered as their JSON form. String literals are quoted, except for set
+/// and variable references and IP addresses.
+pub(crate) fn render_object(obj: &NfObject) -> String {
+    let value = serde_json::to_value(obj).expect("failed to serialize NfObject");
+    let (verb, body) = match single_property(&value) {
+        Some((name, body)) if matches!(obj, NfObject::CmdObject(_)) => (Some(name), body),
+        _ => (None, &value),
+    };
+    let object = match single_property(body) {
+        Some((kind, properties)) => render_list_object(kind, properties),
+        None => render_json(body),
+    };
+    match verb {
+        Some(verb) => format!("{} {}", verb, object),
+        None => object,
+    }
+}
+
+/// Renders a ruleset element given by its kind (e.g. `"chain"`) and properties.
+fn render_list_object(kind: &str, properties: &Value) -> String {
+    let mut words = vec![kind.to_string()];
+    let properties = match properties {
+        Value::Object(properties) => properties,
+        // e.g. `flush ruleset` or lists of counters to reset
+        _ => return kind.to_string(),
+    };
+    for key in ["family", "table", "chain", "name"] {
+        if let Some(value) = properties.get(key) {
+            words.push(render_expr(value));
+        }
+    }
+    if let Some(newname) = properties.get("newname") {
+        words.push(render_expr(newname));
+    }
+    for key in ["handle", "index"] {
+        if let Some(value) = properties.get(key) {
+            words.push(format!("{} {}", key, render_expr(value)));
+        }
+    }
+    let details = match kind {
+        "chain" => render_chain_details(properties),
+        "set" | "map" => render_set_details(properties),
+        "element" => properties.get("elem").map(render_literal),
+        "rule" => render_rule_details(properties),
+        _ => None,
+    };
+    words.extend(details);
+    words.join(" ")
+}
+
+/// Renders the type, hook and policy of a base chain.
+fn render_chain_details(chain: &Map<String, Value>) -> Option<String> {
+    let mut specs = Vec::new();
+    if let (Some(chain_type), Some(hook)) = (chain.get("type"), chain.get("hook")) {
+        let mut spec = format!(
+            "type {} hook {}",
+            render_expr(chain_type),
+            render_expr(hook)
+        );
+        if let Some(dev) = chain.get("dev") {
+            spec += &format!(" devices = {}", render_literal(dev));
+        }
+        if let Some(prio) = chain.get("prio") {
+            spec += &format!(" priority {}", render_expr(prio));
+        }
+        specs.push(spec);
+    }
+    if let Some(policy) = chain.get("policy") {
+        specs.push(format!("policy {}", render_expr(policy)));
+    }
+    render_block(specs)
+}
+
+/// Renders the type and flags of a set or map.
+fn render_set_details(set: &Map<String, Value>) -> Option<String> {
+    let mut specs = Vec::new();
+    if let Some(set_type) = set.get("type") {
+        let mut spec = format!("type {}", render_type(set_type));
+        if let Some(map) = set.get("map") {
+            spec += &format!(" : {}", render_type(map));
+        }
+        specs.push(spec);
+    }
+    if let Some(Value::Array(flags)) = set.get("flags") {
+        let mut flags: Vec<String> = flags.iter().map(render_expr).collect();
+        flags.sort();
+        specs.push(format!("flags {}", flags.join(", ")));
+    }
+    if let Some(elem) = set.get("elem") {
+        specs.push(format!("elements = {}", render_literal(elem)));
+    }
+    render_block(specs)
+}
+
+/// Renders the statements and comment of a rule.
+fn render_rule_details(rule: &Map<String, Value>) -> Option<String> {
+    let mut words: Vec<String> = match rule.get("expr") {
+        Some(Value::Array(statements)) => statements.iter().map(render_stmt).collect(),
+        _ => Vec::new(),
+    };
+    if let Some(comment) = rule.get("comment") {
+        words.push(format!("comment {}", comment));
+    }
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+/// Renders `{ spec; spec; }`, or nothing if there are no specs.
+fn render_block(specs: Vec<String>) -> Option<String> {
+    if specs.is_empty() {
+        None
+    } else {
+        Some(format!("{{ {}; }}", specs.join("; ")))
+    }
+}
+
+/// Renders a single or concatenated set type.
+fn render_type(value: &Value) -> String {
+    match value {
+        Value::Array(types) => types
+            .iter()
+            .map(render_expr)
+            .collect::<Vec<_>>()
+            .join(" . "),
+        other => render_expr(other),
+    }
+}
+
+/// Renders a statement.
+fn render_stmt(value: &Value) -> String {
+    let (name, body) = match single_property(value) {
+        Some(property) => property,
+        None => return render_json(value),
+    };
+    match (name, body) {
+        ("match", Value::Object(m)) => {
+            let left = m.get("left").map(render_expr).unwrap_or_default();
+            let right = m.get("right").map(render_literal).unwrap_or_default();
+            match m.get("op").and_then(Value::as_str) {
+                Some("==") | Some("in") | None => format!("{} {}", left, right),
+                Some(op) => format!("{} {} {}", left, op, right),
+            }
+        }
+        ("mangle", Value::Object(m)) => match (m.get("key"), m.get("value")) {
+            (Some(key), Some(value)) => {
+                format!("{} set {}", render_expr(key), render_literal(value))
+            }
+            _ => render_json(value),
+        },
+        ("counter", Value::String(name)) => format!("counter name {}", quote(name)),
+        ("counter", _) => "counter".to_string(),
+        ("log", Value::Object(log)) => match log.get("prefix") {
+            Some(prefix) => format!("log prefix {}", prefix),
+            None => "log".to_string(),
+        },
+        ("reject", Value::Object(reject)) => match reject.get("type") {
+            Some(reject_type) => {
+                let mut words = vec!["reject with".to_string(), render_expr(reject_type)];
+                words.extend(reject.get("expr").map(render_expr));
+                words.join(" ")
+            }
+            None => "reject".to_string(),
+        },
+        ("snat" | "dnat" | "masquerade" | "redirect", Value::Object(nat)) => {
+            let addr = nat.get("addr").map(render_literal).unwrap_or_default();
+            match nat.get("port") {
+                Some(port) => format!("{} to {}:{}", name, addr, render_literal(port)),
+                None if !addr.is_empty() => format!("{} to {}", name, addr),
+                None => name.to_string(),
+            }
+        }
+        ("ct helper" | "ct timeout" | "ct expectation", value) => {
+            format!("{} set {}", name, render_literal(value))
+        }
+        (_, Value::Null) => name.to_string(),
+        _ => render_verdict(name, body).unwrap_or_else(|| render_json(value)),
+    }
+}
+
+/// Renders an expression.
+fn render_expr(value: &Value) -> String {
+    let (name, body) = match value {
+        Value::String(s) => return s.clone(),
+        Value::Array(items) => return format!("{{ {} }}", join(items, ", ")),
+        Value::Object(_) => match single_property(value) {
+            Some(property) => property,
+            None => return render_json(value),
+        },
+        other => return other.to_string(),
+    };
+    if let Some(verdict) = render_verdict(name, body) {
+        return verdict;
+    }
+    match (name, body) {
+        ("set", Value::Array(items)) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| match item {
+                    Value::Array(mapping) if mapping.len() == 2 => {
+                        format!(
+                            "{} : {}",
+                            render_literal(&mapping[0]),
+                            render_literal(&mapping[1])
+                        )
+                    }
+                    other => render_literal(other),
+                })
+                .collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+        ("set", other) => render_literal(other),
+        ("concat", Value::Array(items)) => join(items, " . "),
+        ("range", Value::Array(bounds)) => join(bounds, "-"),
+        ("&" | "|" | "^" | "<<" | ">>", Value::Array(operands)) => {
+            join(operands, &format!(" {} ", name))
+        }
+        ("prefix", Value::Object(prefix)) => match (prefix.get("addr"), prefix.get("len")) {
+            (Some(addr), Some(len)) => format!("{}/{}", render_literal(addr), len),
+            _ => render_json(value),
+        },
+        ("payload", Value::Object(payload)) => {
+            match (payload.get("protocol"), payload.get("field")) {
+                (Some(protocol), Some(field)) => {
+                    format!("{} {}", render_expr(protocol), render_expr(field))
+                }
+                _ => render_json(value),
+            }
+        }
+        ("meta", Value::Object(meta)) => match meta.get("key") {
+            Some(key) => format!("meta {}", render_expr(key)),
+            None => render_json(value),
+        },
+        ("ct", Value::Object(ct)) => {
+            let mut words = vec!["ct".to_string()];
+            for key in ["family", "dir", "key"] {
+                words.extend(ct.get(key).map(render_expr));
+            }
+            words.join(" ")
+        }
+        ("map", Value::Object(map)) => match (map.get("key"), map.get("data")) {
+            (Some(key), Some(data)) => {
+                format!("{} map {}", render_expr(key), render_literal(data))
+            }
+            _ => render_json(value),
+        },
+        ("elem", Value::Object(elem)) => {
+            let mut words: Vec<String> = elem.get("val").map(render_literal).into_iter().collect();
+            for key in ["timeout", "expires"] {
+                if let Some(seconds) = elem.get(key) {
+                    words.push(format!("{} {}s", key, seconds));
+                }
+            }
+            if let Some(comment) = elem.get("comment") {
+                words.push(format!("comment {}", comment));
+            }
+            words.join(" ")
+        }
+        _ => render_json(value),
+    }
+}
+
+/// Renders a verdict, or returns `None` if `name` is not a verdict.
+fn render_verdict(name: &str, body: &Value) -> Option<String> {
+    match name {
+        "accept" | "drop" | "continue" | "return" => Some(name.to_string()),
+        "jump" | "goto" => body
+            .get("target")
+            .map(|target| format!("{} {}", name, render_expr(target))),
+        _ => None,
+    }
+}
+
+/// Renders an expression in a value position, where strings are literals
+/// that nft expects quoted.
+fn render_literal(value: &Value) -> String {
+    match value {
+        Value::String(s) if is_bare_literal(s) => s.clone(),
+        Value::String(s) => quote(s),
+        other => render_expr(other),
+    }
+}
+
+/// Returns whether a string literal is a set or variable reference or an IP
+/// address (optionally with prefix length), which nft lists unquoted.
+fn is_bare_literal(s: &str) -> bool {
+    if s.starts_with('@') || s.starts_with('$') {
+        return true;
+    }
+    let (addr, len) = match s.split_once('/') {
+        Some((addr, len)) => (addr, Some(len)),
+        None => (s, None),
+    };
+    addr.parse::<IpAddr>().is_ok() && len.map_or(true, |len| len.parse::<u8>().is_ok())
+}
+
+/// Quotes a string, escaping quotes, backslashes and control characters.
+fn quote(s: &str) -> String {
+    Value::String(s.to_string()).to_string()
+}
+
+/// Renders the given values, separated by `separator`.
+fn join(items: &[Value], separator: &str) -> String {
+    items
+        .iter()
+        .map(render_literal)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Renders a value without nft syntax as compact JSON.
+fn render_json(value: &Value) -> String {
+    value.to_string()
+}
+
+/// Returns name and value of an object with exactly one property,
+/// which is how nftables JSON tags commands, objects, statements and expressions.
+fn single_property(value: &Value) -> Option<(&str, &Value)> {
+    match value {
+        Value::Object(map) if map.len() == 1 => map.iter().next().map(|(k, v)| (k.as_str(), v)),
+        _ => None,
+    }
+}
@@ -1,16 +1,42 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::net::IpAddr;
+use std::str::FromStr;
+use strum::{IntoEnumIterator, VariantNames};
+use strum_macros::{AsRefStr, EnumString};
 
 use crate::stmt::{Counter, JumpTarget, Statement};
+use crate::types::NfTimeUnit;
+use crate::validation::ValidationError;
+use crate::visitor::{serialize_none, single_string_to_hashset};
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
+#[non_exhaustive]
 /// Expressions are the building blocks of (most) statements.
 /// In their most basic form, they are just immediate values represented as a JSON string, integer or boolean type.
+///
+/// New kinds of expressions may be added in minor releases, so matches on
+/// this enum need a wildcard arm. The `as_*` accessors, e.g.
+/// [Expression::as_named], avoid matching altogether.
 pub enum Expression {
     // immediates
+    /// A string, e.g. an interface name, or a set reference like `@blocked`.
+    ///
+    /// Strings are passed to nft as they are, JSON escaping is handled when
+    /// serializing. For interface names, nft treats a trailing `*` as a
+    /// wildcard, i.e. `"eth*"` matches `eth0`, `eth1` and so on; a `*`
+    /// elsewhere, or a trailing `\*`, is taken literally.
     String(String),
-    Number(u32),
+    /// An integer, e.g. a port or a byte count.
+    ///
+    /// 64-bit values are needed e.g. for `ct bytes` or `ct packets`.
+    Number(u64),
+    /// A boolean, compared against expressions checking for presence.
+    ///
+    /// E.g. `fib saddr . iif oif exists` (or `missing`) matches a [fib](Fib)
+    /// lookup against `true` (or `false`). [Extension header](Exthdr) and
+    /// [TCP option](TcpOption) matches without field use booleans likewise.
     Boolean(bool),
     /// List expressions are constructed by plain arrays containing of an arbitrary number of expressions.
     List(Vec<Expression>),
@@ -21,9 +47,159 @@ pub enum Expression {
     Verdict(Verdict),
 }
 
+impl Expression {
+    /// Looks up `key` in the named map `map`, i.e. `<key> map @<map>`.
+    ///
+    /// `map` is the map's name, a leading `@` is added if missing.
+    pub fn map_lookup(key: impl Into<Expression>, map: impl Into<String>) -> Expression {
+        let mut map = map.into();
+        if !map.starts_with('@') {
+            map.insert(0, '@');
+        }
+        Expression::Named(NamedExpression::Map(Box::new(Map {
+            key: key.into(),
+            data: Expression::String(map),
+        })))
+    }
+
+    /// Returns the string if this is a [string immediate](Expression::String).
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Expression::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if this is a [number immediate](Expression::Number).
+    pub fn as_number(&self) -> Option<u64> {
+        match self {
+            Expression::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the named expression if this is a [named expression](Expression::Named).
+    pub fn as_named(&self) -> Option<&NamedExpression> {
+        match self {
+            Expression::Named(named) => Some(named),
+            _ => None,
+        }
+    }
+
+    /// Creates a [concatenation](NamedExpression::Concat) of the given expressions,
+    /// e.g. `ip saddr . tcp dport`.
+    ///
+    /// When matching against or adding to a set with a
+    /// [concatenated type](crate::schema::SetTypeValue::Concatenated), the
+    /// components have to be given in the order of the set type.
+    pub fn concat<I>(components: I) -> Expression
+    where
+        I: IntoIterator,
+        I::Item: Into<Expression>,
+    {
+        Expression::Named(NamedExpression::Concat(
+            components.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// Creates an anonymous set of IPv4 or IPv6 prefixes,
+    /// e.g. `{ 10.0.0.0/8, 192.168.0.0/16 }`.
+    ///
+    /// nftables implies interval semantics for anonymous sets holding
+    /// prefixes, so no set flags are needed.
+    pub fn prefix_set(prefixes: impl IntoIterator<Item = (IpAddr, u32)>) -> Expression {
+        Expression::Named(NamedExpression::Set(
+            prefixes
+                .into_iter()
+                .map(|(addr, len)| {
+                    SetItem::Element(Expression::Named(NamedExpression::Prefix(Prefix::new(
+                        addr, len,
+                    ))))
+                })
+                .collect(),
+        ))
+    }
+
+    /// Creates a time value, e.g. for comparisons with `ct expiration`.
+    ///
+    /// nftables expects time values as a number of seconds in JSON,
+    /// i.e. `Expression::time(2, NfTimeUnit::Minute)` is `120`.
+    /// Values too large to be represented in seconds are clamped to `u64::MAX`.
+    pub fn time(value: u64, unit: NfTimeUnit) -> Expression {
+        Expression::Number(value.saturating_mul(unit.as_seconds()))
+    }
+
+    /// Returns the number of components of a concatenation, or 1 for any other expression.
+    ///
+    /// Compare with [SetTypeValue::arity](crate::schema::SetTypeValue::arity)
+    /// to check that a key fits a set.
+    pub fn arity(&self) -> usize {
+        match self {
+            Expression::Named(NamedExpression::Concat(components)) => components.len(),
+            Expression::Named(NamedExpression::Elem(elem)) => elem.val.arity(),
+            _ => 1,
+        }
+    }
+
+    /// Creates an element of a named map, mapping `key` to `value`.
+    ///
+    /// Either side may be a [concatenation](NamedExpression::Concat) if the
+    /// map's key or value type is [concatenated](crate::schema::SetTypeValue::Concatenated).
+    pub fn map_element(key: impl Into<Expression>, value: impl Into<Expression>) -> Expression {
+        Expression::List(vec![key.into(), value.into()])
+    }
+
+    /// Combines the expression with `rhs` using binary AND (`&`).
+    pub fn and(self, rhs: impl Into<Expression>) -> Expression {
+        Expression::BinaryOperation(BinaryOperation::AND(Box::new(self), Box::new(rhs.into())))
+    }
+
+    /// Combines the expression with `rhs` using binary OR (`|`).
+    pub fn or(self, rhs: impl Into<Expression>) -> Expression {
+        Expression::BinaryOperation(BinaryOperation::OR(Box::new(self), Box::new(rhs.into())))
+    }
+
+    /// Combines the expression with `rhs` using binary XOR (`^`).
+    pub fn xor(self, rhs: impl Into<Expression>) -> Expression {
+        Expression::BinaryOperation(BinaryOperation::XOR(Box::new(self), Box::new(rhs.into())))
+    }
+
+    /// Shifts the expression left by `rhs` bits (`<<`).
+    pub fn lshift(self, rhs: impl Into<Expression>) -> Expression {
+        Expression::BinaryOperation(BinaryOperation::LSHIFT(
+            Box::new(self),
+            Box::new(rhs.into()),
+        ))
+    }
+
+    /// Shifts the expression right by `rhs` bits (`>>`).
+    pub fn rshift(self, rhs: impl Into<Expression>) -> Expression {
+        Expression::BinaryOperation(BinaryOperation::RSHIFT(
+            Box::new(self),
+            Box::new(rhs.into()),
+        ))
+    }
+}
+
+impl From<u32> for Expression {
+    fn from(n: u32) -> Self {
+        Expression::Number(n.into())
+    }
+}
+
+impl From<NamedExpression> for Expression {
+    fn from(expr: NamedExpression) -> Self {
+        Expression::Named(expr)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 /// Wrapper for non-immediate `Expression`s.
+///
+/// Support for further nftables expressions is added over time;
+/// matches on this enum need a wildcard arm.
 pub enum NamedExpression {
     /// Concatenate several expressions.
     Concat(Vec<Expression>),
@@ -54,7 +230,24 @@ pub enum NamedExpression {
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "map")]
-/// Map a key to a value.
+/// Map a key to a value, i.e. a pure lookup such as `ip saddr map @m`.
+///
+/// The lookup does not change the map. To add or update elements of a
+/// named map from the packet path, use the [map statement](crate::stmt::MapUpdate).
+///
+/// ```
+/// use nftables::expr::{Expression, NamedExpression, Payload, PayloadField, PayloadProtocol};
+///
+/// // ip saddr map @m
+/// let saddr = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+///     PayloadField::new(PayloadProtocol::Ip, "saddr"),
+/// )));
+/// let lookup = Expression::map_lookup(saddr, "m");
+/// assert_eq!(
+///     r#"{"map":{"key":{"payload":{"protocol":"ip","field":"saddr"}},"data":"@m"}}"#,
+///     serde_json::to_string(&lookup).unwrap()
+/// );
+/// ```
 pub struct Map {
     /// Map key.
     pub key: Expression,
@@ -62,16 +255,38 @@ pub struct Map {
     pub data: Expression,
 }
 
+impl Map {
+    /// Creates a lookup of `key` in an anonymous map of the given key/value pairs,
+    /// i.e. `<key> map { <k> : <v>, ... }`.
+    pub fn anonymous(
+        key: Expression,
+        mappings: impl IntoIterator<Item = (Expression, Expression)>,
+    ) -> Map {
+        Map {
+            key,
+            data: Expression::Named(NamedExpression::Set(
+                mappings
+                    .into_iter()
+                    .map(|(k, v)| SetItem::Mapping(k, v))
+                    .collect(),
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 /// Item in an anonymous set.
+///
+/// When parsing, an array of exactly two items is read as a mapping,
+/// as nftables uses this form for map elements.
 pub enum SetItem {
-    /// A set item containing a single expression.
-    Element(Expression),
     /// A set item mapping two expressions.
     Mapping(Expression, Expression),
     /// A set item mapping an expression to a statement.
     MappingStatement(Expression, Statement),
+    /// A set item containing a single expression.
+    Element(Expression),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -82,14 +297,49 @@ pub struct Prefix {
     pub len: u32,
 }
 
+impl Prefix {
+    /// Creates the prefix `<addr>/<len>`.
+    pub fn new(addr: IpAddr, len: u32) -> Prefix {
+        Prefix {
+            addr: Box::new(Expression::String(addr.to_string())),
+            len,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "range")]
 /// Construct a range of values.
 /// The first array item denotes the lower boundary, the second one the upper boundary.
+///
+/// Both boundaries are inclusive.
 pub struct Range {
     pub range: Vec<Expression>,
 }
 
+impl Range {
+    /// Creates a range from `lo` to `hi`, both inclusive.
+    pub fn new(lo: impl Into<Expression>, hi: impl Into<Expression>) -> Range {
+        Range {
+            range: vec![lo.into(), hi.into()],
+        }
+    }
+}
+
+/// Converts e.g. `1024..=65535` into a range of numbers.
+impl From<std::ops::RangeInclusive<u32>> for Range {
+    fn from(range: std::ops::RangeInclusive<u32>) -> Self {
+        let (lo, hi) = range.into_inner();
+        Range::new(lo, hi)
+    }
+}
+
+impl From<std::ops::RangeInclusive<u32>> for Expression {
+    fn from(range: std::ops::RangeInclusive<u32>) -> Self {
+        Expression::Range(range.into())
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Payload {
@@ -111,10 +361,199 @@ pub struct PayloadRaw {
 /// Construct a payload expression, i.e. a reference to a certain part of packet data.
 /// Allows to reference a field by name (`field`) in a named packet header (`protocol`).
 pub struct PayloadField {
-    pub protocol: String,
+    pub protocol: PayloadProtocol,
     pub field: String,
 }
 
+impl PayloadField {
+    /// Creates a reference to `field` in the header of `protocol`, e.g. `(PayloadProtocol::Tcp, "dport")`.
+    pub fn new(protocol: impl Into<PayloadProtocol>, field: impl Into<String>) -> PayloadField {
+        PayloadField {
+            protocol: protocol.into(),
+            field: field.into(),
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+    strum_macros::VariantNames,
+)]
+#[serde(from = "String", into = "String")]
+#[strum(serialize_all = "lowercase")]
+/// A protocol header referenced by a [payload field](PayloadField).
+///
+/// Headers without a variant of their own are kept as [PayloadProtocol::Other].
+/// Converting or deserializing a string picks the named variant if there is one,
+/// so `Other` never holds the name of a known protocol.
+pub enum PayloadProtocol {
+    Ether,
+    Vlan,
+    Arp,
+    Ip,
+    Icmp,
+    Igmp,
+    Ip6,
+    Icmpv6,
+    Ah,
+    Esp,
+    Comp,
+    Udp,
+    Udplite,
+    Tcp,
+    Dccp,
+    Sctp,
+    /// Transport header of any protocol with ports, i.e. `th`.
+    Th,
+    #[strum(default)]
+    /// Any other protocol header, by name.
+    Other(String),
+}
+
+impl From<&str> for PayloadProtocol {
+    fn from(name: &str) -> Self {
+        // VARIANTS holds the names `Display` uses, in the order of `iter()`.
+        PayloadProtocol::VARIANTS
+            .iter()
+            .zip(PayloadProtocol::iter())
+            .find(|(known, protocol)| {
+                !matches!(protocol, PayloadProtocol::Other(_)) && **known == name
+            })
+            .map(|(_, protocol)| protocol)
+            .unwrap_or_else(|| PayloadProtocol::Other(name.to_string()))
+    }
+}
+
+impl From<String> for PayloadProtocol {
+    fn from(name: String) -> Self {
+        PayloadProtocol::from(name.as_str())
+    }
+}
+
+impl From<PayloadProtocol> for String {
+    fn from(protocol: PayloadProtocol) -> Self {
+        protocol.to_string()
+    }
+}
+
+/// Header fields known to nftables, by protocol.
+const PAYLOAD_FIELDS: &[(&str, &[&str])] = &[
+    ("ether", &["daddr", "saddr", "type"]),
+    ("vlan", &["id", "dei", "cfi", "pcp", "type"]),
+    (
+        "arp",
+        &[
+            "htype",
+            "ptype",
+            "hlen",
+            "plen",
+            "operation",
+            "saddr ether",
+            "saddr ip",
+            "daddr ether",
+            "daddr ip",
+        ],
+    ),
+    (
+        "ip",
+        &[
+            "version",
+            "hdrlength",
+            "dscp",
+            "ecn",
+            "length",
+            "id",
+            "frag-off",
+            "ttl",
+            "protocol",
+            "checksum",
+            "saddr",
+            "daddr",
+        ],
+    ),
+    (
+        "icmp",
+        &[
+            "type", "code", "checksum", "id", "sequence", "gateway", "mtu",
+        ],
+    ),
+    ("igmp", &["type", "mrt", "checksum", "group"]),
+    (
+        "ip6",
+        &[
+            "version",
+            "dscp",
+            "ecn",
+            "flowlabel",
+            "length",
+            "nexthdr",
+            "hoplimit",
+            "saddr",
+            "daddr",
+        ],
+    ),
+    (
+        "icmpv6",
+        &[
+            "type",
+            "code",
+            "checksum",
+            "parameter-problem",
+            "packet-too-big",
+            "id",
+            "sequence",
+            "max-delay",
+            "taddr",
+            "daddr",
+        ],
+    ),
+    (
+        "ah",
+        &["nexthdr", "hdrlength", "reserved", "spi", "sequence"],
+    ),
+    ("esp", &["spi", "sequence"]),
+    ("comp", &["nexthdr", "flags", "cpi"]),
+    ("udp", &["sport", "dport", "length", "checksum"]),
+    ("udplite", &["sport", "dport", "csumcov", "checksum"]),
+    (
+        "tcp",
+        &[
+            "sport", "dport", "sequence", "ackseq", "doff", "reserved", "flags", "window",
+            "checksum", "urgptr",
+        ],
+    ),
+    ("dccp", &["sport", "dport", "type"]),
+    ("sctp", &["sport", "dport", "vtag", "checksum"]),
+    ("th", &["sport", "dport"]),
+];
+
+impl PayloadField {
+    /// Checks that `protocol` is a header known to nftables and `field` is one of its fields.
+    ///
+    /// Construction of payload fields is not restricted, this check has to be called explicitly.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let (_, fields) = PAYLOAD_FIELDS
+            .iter()
+            .find(|(protocol, _)| PayloadProtocol::from(*protocol) == self.protocol)
+            .ok_or_else(|| ValidationError::UnknownPayloadProtocol(self.protocol.to_string()))?;
+        if !fields.contains(&self.field.as_str()) {
+            return Err(ValidationError::UnknownPayloadField {
+                protocol: self.protocol.to_string(),
+                field: self.field.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Represents a protocol layer for `payload` references.
@@ -147,6 +586,51 @@ pub struct TcpOption {
     pub field: String,
 }
 
+/// Known TCP options and their fields, as accepted by nftables.
+const TCP_OPTION_FIELDS: &[(&str, &[&str])] = &[
+    ("eol", &["kind"]),
+    ("nop", &["kind"]),
+    ("maxseg", &["kind", "length", "size"]),
+    ("window", &["kind", "length", "count"]),
+    ("sack-perm", &["kind", "length"]),
+    ("sack", &["kind", "length", "left", "right"]),
+    ("sack0", &["kind", "length", "left", "right"]),
+    ("sack1", &["kind", "length", "left", "right"]),
+    ("sack2", &["kind", "length", "left", "right"]),
+    ("sack3", &["kind", "length", "left", "right"]),
+    ("timestamp", &["kind", "length", "tsval", "tsecr"]),
+    ("fastopen", &["kind", "length"]),
+    ("md5sig", &["kind", "length"]),
+    ("mptcp", &["kind", "length", "subtype"]),
+];
+
+impl TcpOption {
+    /// Creates a reference to `field` of the TCP option `name`, e.g. `("maxseg", "size")`.
+    pub fn new(name: impl Into<String>, field: impl Into<String>) -> TcpOption {
+        TcpOption {
+            name: name.into(),
+            field: field.into(),
+        }
+    }
+
+    /// Checks that `name` is a TCP option known to nftables and `field` is one of its fields.
+    ///
+    /// Like [PayloadField::validate], this check has to be called explicitly.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let (_, fields) = TCP_OPTION_FIELDS
+            .iter()
+            .find(|(name, _)| *name == self.name)
+            .ok_or_else(|| ValidationError::UnknownTcpOption(self.name.clone()))?;
+        if !fields.contains(&self.field.as_str()) {
+            return Err(ValidationError::UnknownTcpOptionField {
+                option: self.name.clone(),
+                field: self.field.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "sctp chunk")]
 /// Create a reference to a field (`field`) of an SCTP chunk (`name`).
@@ -164,7 +648,10 @@ pub struct Meta {
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 /// Represents a `meta` key for packet meta data.
+///
+/// Keys introduced by newer nftables releases may be added in minor releases.
 pub enum MetaKey {
     Length,
     Protocol,
@@ -172,27 +659,128 @@ pub enum MetaKey {
     Random,
     Mark,
     Iif,
+    /// Name of the input interface. Matched against a string, which may
+    /// end in a `*` wildcard (see [Expression::String]).
     Iifname,
     Iiftype,
     Oif,
+    /// Name of the output interface. Matched against a string, which may
+    /// end in a `*` wildcard (see [Expression::String]).
     Oifname,
     Oiftype,
     Skuid,
     Skgid,
     Nftrace,
     Rtclassid,
-    Ibriport,
-    Obriport,
-    Ibridgename,
-    Obridgename,
+    /// Name of the bridge the packet was received on.
+    ///
+    /// Older nftables releases called this key `ibriport` or `ibridgename`,
+    /// which is accepted when deserializing.
+    #[serde(alias = "ibriport", alias = "ibridgename")]
+    Ibrname,
+    /// Name of the bridge the packet will be sent from.
+    ///
+    /// Older nftables releases called this key `obriport` or `obridgename`,
+    /// which is accepted when deserializing.
+    #[serde(alias = "obriport", alias = "obridgename")]
+    Obrname,
+    /// VLAN ID of the bridge port the packet was received on.
+    Ibrpvid,
+    /// VLAN protocol of the bridge the packet was received on.
+    Ibrvproto,
     Pkttype,
     Cpu,
     Iifgroup,
     Oifgroup,
+    /// net_cls classid of the socket's cgroup (cgroup v1), e.g. `0x00100001` for `10:1`.
+    ///
+    /// cgroup v2 paths are matched with [Socket::cgroupv2] instead.
     Cgroup,
     Nfproto,
     L4proto,
     Secpath,
+    /// Security mark of the packet, set from a named `secmark` object
+    /// (see [Mangle::secmark](crate::stmt::Mangle::secmark)).
+    Secmark,
+    /// Time of packet reception, compared against a date string such as
+    /// `"2023-01-01 00:00:00"` or a UNIX timestamp.
+    Time,
+    /// Day of week of packet reception, compared against a [Weekday].
+    Day,
+    /// Hour of day of packet reception, compared against a [TimeOfDay]
+    /// or a [range](Range) of them.
+    Hour,
+}
+
+#[allow(non_upper_case_globals)]
+impl MetaKey {
+    #[deprecated(note = "renamed to `MetaKey::Ibrname`")]
+    /// Former name of [MetaKey::Ibrname].
+    pub const Ibriport: MetaKey = MetaKey::Ibrname;
+    #[deprecated(note = "renamed to `MetaKey::Obrname`")]
+    /// Former name of [MetaKey::Obrname].
+    pub const Obriport: MetaKey = MetaKey::Obrname;
+    #[deprecated(note = "renamed to `MetaKey::Ibrname`")]
+    /// Former name of [MetaKey::Ibrname].
+    pub const Ibridgename: MetaKey = MetaKey::Ibrname;
+    #[deprecated(note = "renamed to `MetaKey::Obrname`")]
+    /// Former name of [MetaKey::Obrname].
+    pub const Obridgename: MetaKey = MetaKey::Obrname;
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, AsRefStr)]
+/// Day of week as matched by `meta day`.
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<Weekday> for Expression {
+    fn from(day: Weekday) -> Self {
+        Expression::String(day.as_ref().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+/// Time of day as matched by `meta hour`.
+///
+/// Converts into a string expression of the form `"HH:MM"`, or `"HH:MM:SS"`
+/// if seconds are given.
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl TimeOfDay {
+    /// Creates a time of day at the start of the given minute.
+    pub fn new(hour: u8, minute: u8) -> TimeOfDay {
+        TimeOfDay {
+            hour,
+            minute,
+            second: 0,
+        }
+    }
+
+    /// Creates a range expression matching from `start` to `end` (inclusive).
+    pub fn range(start: TimeOfDay, end: TimeOfDay) -> Expression {
+        Expression::Range(Range::new(start, end))
+    }
+}
+
+impl From<TimeOfDay> for Expression {
+    fn from(time: TimeOfDay) -> Self {
+        let s = match time.second {
+            0 => format!("{:02}:{:02}", time.hour, time.minute),
+            _ => format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second),
+        };
+        Expression::String(s)
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -211,6 +799,9 @@ pub enum RTKey {
     ClassId,
     NextHop,
     MTU,
+    /// Whether the packet was transformed by IPsec, matched against a boolean
+    /// (`rt ipsec exists` or `missing`).
+    Ipsec,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -232,6 +823,53 @@ pub struct CT {
     pub dir: Option<CTDir>,
 }
 
+impl CT {
+    /// Creates a reference to the given conntrack key, without family and direction.
+    pub fn new(key: CTKey) -> CT {
+        CT {
+            key: key.as_ref().to_string(),
+            family: None,
+            dir: None,
+        }
+    }
+
+    /// Creates a reference to the name of the connection's helper, i.e. `ct helper`.
+    pub fn helper() -> CT {
+        CT::new(CTKey::Helper)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, AsRefStr, EnumString)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+#[non_exhaustive]
+/// Represents a key of the `ct` expression.
+///
+/// Keys introduced by newer nftables releases may be added in minor releases.
+pub enum CTKey {
+    State,
+    Direction,
+    /// Conntrack status, matched against [CTStatus] flags.
+    Status,
+    Mark,
+    Expiration,
+    Helper,
+    /// Conntrack label, matched against a label name from `connlabel.conf`
+    /// or a label bit number.
+    Label,
+    L3proto,
+    Saddr,
+    Daddr,
+    Protocol,
+    ProtoSrc,
+    ProtoDst,
+    Packets,
+    Bytes,
+    Avgpkt,
+    Zone,
+    Id,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Represents a protocol family for use by the `ct` expression.
@@ -248,6 +886,69 @@ pub enum CTDir {
     Reply,
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, AsRefStr, EnumString)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+/// Represents a conntrack status flag, matched by the `ct status` expression.
+pub enum CTStatus {
+    /// The connection is an expected connection.
+    Expected,
+    /// Packets have been seen in both directions.
+    SeenReply,
+    /// The connection will not expire early.
+    Assured,
+    /// The connection has been confirmed.
+    Confirmed,
+    /// The connection needs source NAT.
+    Snat,
+    /// The connection needs destination NAT.
+    Dnat,
+    /// The connection is about to be removed.
+    Dying,
+}
+
+impl CTStatus {
+    /// Builds the right hand side of a `ct status` match.
+    ///
+    /// A single flag becomes a string, multiple flags become a list.
+    pub fn to_expression(flags: impl IntoIterator<Item = CTStatus>) -> Expression {
+        let mut flags: Vec<Expression> = flags
+            .into_iter()
+            .map(|flag| Expression::String(flag.as_ref().to_string()))
+            .collect();
+        match flags.len() {
+            1 => flags.remove(0),
+            _ => Expression::List(flags),
+        }
+    }
+
+    /// Reads the flags of the right hand side of a `ct status` match.
+    ///
+    /// Accepts a single string, a list or an anonymous set of strings.
+    /// Returns `None` if the expression contains anything else.
+    pub fn from_expression(expr: &Expression) -> Option<HashSet<CTStatus>> {
+        match expr {
+            Expression::String(flag) => CTStatus::from_str(flag).ok().map(|f| HashSet::from([f])),
+            Expression::List(flags) => flags.iter().map(CTStatus::from_single).collect(),
+            Expression::Named(NamedExpression::Set(items)) => items
+                .iter()
+                .map(|item| match item {
+                    SetItem::Element(flag) => CTStatus::from_single(flag),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
+    fn from_single(expr: &Expression) -> Option<CTStatus> {
+        match expr {
+            Expression::String(flag) => CTStatus::from_str(flag).ok(),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "numgen")]
 /// Create a number generator.
@@ -259,6 +960,29 @@ pub struct Numgen {
     pub offset: Option<u32>,
 }
 
+impl Numgen {
+    /// Creates a number generator producing numbers modulo `modulus`, without offset.
+    pub fn new(mode: NgMode, modulus: u32) -> Numgen {
+        Numgen {
+            mode,
+            ng_mod: modulus,
+            offset: None,
+        }
+    }
+
+    /// Sets the offset added to the generated numbers.
+    pub fn offset(mut self, offset: u32) -> Numgen {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+impl From<Numgen> for Expression {
+    fn from(numgen: Numgen) -> Self {
+        Expression::Named(NamedExpression::Numgen(numgen))
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Represents a number generator mode.
@@ -280,6 +1004,36 @@ pub struct JHash {
     pub seed: Option<u32>,
 }
 
+impl JHash {
+    /// Creates a hash of `expr` modulo `modulus`, without seed and offset.
+    pub fn new(modulus: u32, expr: impl Into<Expression>) -> JHash {
+        JHash {
+            hash_mod: modulus,
+            offset: None,
+            expr: Box::new(expr.into()),
+            seed: None,
+        }
+    }
+
+    /// Sets the seed of the hash function.
+    pub fn seed(mut self, seed: u32) -> JHash {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the offset added to the hash value.
+    pub fn offset(mut self, offset: u32) -> JHash {
+        self.offset = Some(offset);
+        self
+    }
+}
+
+impl From<JHash> for Expression {
+    fn from(jhash: JHash) -> Self {
+        Expression::Named(NamedExpression::JHash(jhash))
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "symhash")]
 /// Hash packet data
@@ -294,6 +1048,8 @@ pub struct SymHash {
 /// Perform kernel Forwarding Information Base lookups.
 pub struct Fib {
     pub result: FibResult,
+    #[serde(deserialize_with = "single_string_to_hashset")]
+    /// Flags of the lookup. A single flag may be given as plain string.
     pub flags: HashSet<FibFlag>,
 }
 
@@ -301,8 +1057,11 @@ pub struct Fib {
 #[serde(rename_all = "lowercase")]
 /// Represents which data is queried by `fib` lookup.
 pub enum FibResult {
+    /// Index of the output interface.
     Oif,
+    /// Name of the output interface.
     Oifname,
+    /// Address type, e.g. `"local"` or `"unicast"`.
     Type,
 }
 
@@ -320,6 +1079,10 @@ pub enum FibFlag {
     Iif,
     /// Consider the packet's output interface.
     Oif,
+    /// Only check whether a result exists, as in `fib daddr oif exists`.
+    ///
+    /// Set by newer nftables releases when listing such matches.
+    Present,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -349,10 +1112,17 @@ pub enum BinaryOperation {
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Verdict expression.
+///
+/// Verdicts without a target serialize as `{"<verdict>": null}` like nftables
+/// emits them, so they are not mistaken for strings when parsed again.
 pub enum Verdict {
+    #[serde(serialize_with = "serialize_none")]
     Accept,
+    #[serde(serialize_with = "serialize_none")]
     Drop,
+    #[serde(serialize_with = "serialize_none")]
     Continue,
+    #[serde(serialize_with = "serialize_none")]
     Return,
     Jump(JumpTarget),
     Goto(JumpTarget),
@@ -363,9 +1133,14 @@ pub enum Verdict {
 /// Explicitly set element object.
 pub struct Elem {
     pub val: Box<Expression>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub timeout: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expires: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// Element comment, e.g. `1.2.3.4 comment "x"`.
     pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub counter: Option<Counter>,
 }
 
@@ -374,6 +1149,20 @@ pub struct Elem {
 /// Construct a reference to packet’s socket.
 pub struct Socket {
     pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Ancestor level of the cgroup v2 path, for the `cgroupv2` key only.
+    pub level: Option<u32>,
+}
+
+impl Socket {
+    /// Creates a reference to the cgroup v2 ancestor of the packet's socket
+    /// at the given level, i.e. `socket cgroupv2 level <level>`.
+    pub fn cgroupv2(level: u32) -> Socket {
+        Socket {
+            key: "cgroupv2".to_string(),
+            level: Some(level),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -1,15 +1,41 @@
 use std::string::FromUtf8Error;
 use std::{
-    io::{self, Write},
-    process::{Command, Stdio},
+    borrow::Cow,
+    env,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdout, Command, Stdio},
 };
+#[cfg(unix)]
+use std::{fmt, os::unix::process::CommandExt, sync::Arc};
 
 use thiserror::Error;
 
-use crate::schema::Nftables;
+use crate::compat::{self, CompatMode};
+use crate::error::ParseError;
+use crate::schema::{MonitorEvent, NfObject, Nftables};
+use crate::types::{NfFamily, NfObjectKind};
 
 const NFT_EXECUTABLE: &str = "nft"; // search in PATH
 
+/// Environment variable overriding the path of the nft binary.
+pub const NFT_BIN_ENV: &str = "NFT_BIN";
+
+/// Locations of the nft binary checked if it is not found in `PATH`.
+const NFT_FALLBACK_PATHS: &[&str] = &["/usr/sbin/nft", "/sbin/nft", "/usr/local/sbin/nft"];
+
+/// Maximum number of bytes of nft's stdout/stderr shown when displaying an error.
+const MAX_OUTPUT_DISPLAY_LEN: usize = 4096;
+
+/// Logs at debug level if the `log` feature is enabled, and compiles to nothing otherwise.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+    };
+}
+
 #[derive(Error, Debug)]
 pub enum NftablesError {
     #[error("unable to execute {program}: {inner}")]
@@ -19,15 +45,44 @@ pub enum NftablesError {
         program: String,
         inner: FromUtf8Error,
     },
-    #[error("got invalid json: {0}")]
-    NftInvalidJson(serde_json::Error),
-    #[error("{program} did not return successfully while {hint}")]
+    /// The output of nft, or a document, could not be parsed.
+    #[error(transparent)]
+    NftParse(#[from] ParseError),
+    /// nft exited unsuccessfully.
+    ///
+    /// When displayed, `stdout` and `stderr` are truncated to 4 KiB each.
+    /// The fields always hold the complete output.
+    #[error(
+        "{program} did not return successfully while {hint}\nstdout: {}\nstderr: {}",
+        truncate_output(.stdout),
+        truncate_output(.stderr)
+    )]
     NftFailed {
         program: String,
         hint: String,
         stdout: String,
         stderr: String,
     },
+    /// Applying a ruleset failed at the object with the given index.
+    ///
+    /// Returned by [apply_ruleset_locating_failure].
+    #[error("object {index} of ruleset could not be applied: {inner}")]
+    NftBatchFailed {
+        /// Index of the failing object in [Nftables::objects].
+        index: usize,
+        /// The failing object.
+        object: Box<NfObject>,
+        /// The error reported when applying the whole ruleset.
+        inner: Box<NftablesError>,
+    },
+    /// The ruleset was modified since its fingerprint was taken.
+    ///
+    /// Returned by [apply_if_unchanged].
+    #[error("ruleset changed (expected fingerprint {expected:#x}, found {found:#x})")]
+    NftRulesetChanged { expected: u64, found: u64 },
+    /// Writing the payload to the writer passed to [apply_ruleset_tee] failed.
+    #[error("unable to write payload to tee: {0}")]
+    NftTeeWrite(io::Error),
 }
 
 pub fn get_current_ruleset(
@@ -35,34 +90,146 @@ pub fn get_current_ruleset(
     args: Option<Vec<&str>>,
 ) -> Result<Nftables, NftablesError> {
     let output = get_current_ruleset_raw(program, args)?;
-    serde_json::from_str(&output).map_err(NftablesError::NftInvalidJson)
+    serde_json::from_str(&output).map_err(invalid_json)
+}
+
+/// Like [get_current_ruleset], but also returns the JSON output of nft as-is,
+/// e.g. to preserve it for auditing.
+pub fn get_current_ruleset_with_raw(
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+) -> Result<(Nftables, String), NftablesError> {
+    let output = get_current_ruleset_raw(program, args)?;
+    let nftables = serde_json::from_str(&output).map_err(invalid_json)?;
+    Ok((nftables, output))
+}
+
+/// Like [get_current_ruleset], but adapts output of older nftables releases
+/// according to the given [CompatMode].
+pub fn get_current_ruleset_compat(
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+    mode: CompatMode,
+) -> Result<Nftables, NftablesError> {
+    let output = get_current_ruleset_raw(program, args)?;
+    compat::from_str(&output, mode).map_err(invalid_json)
+}
+
+/// Checks whether the named ruleset element exists, by running `nft list <kind> <family> <table> <name>`.
+///
+/// For [tables](NfObjectKind::Table), `name` is ignored. Returns `Ok(false)`
+/// if nft reports that the element (or its table) does not exist, and an error
+/// for any other failure. nft is run with `LC_ALL=C`, so that its error message
+/// can be recognized regardless of the locale.
+pub fn object_exists(
+    family: NfFamily,
+    kind: NfObjectKind,
+    table: &str,
+    name: &str,
+    program: Option<&str>,
+) -> Result<bool, NftablesError> {
+    let mut args = vec!["list", kind.as_ref(), family.as_ref(), table];
+    if kind != NfObjectKind::Table {
+        args.push(name);
+    }
+    let options = NftListOptions { terse: true };
+    let mut nft_cmd = get_command(program);
+    nft_cmd.env("LC_ALL", "C");
+    match list_raw(&mut nft_cmd, &options, Some(args)) {
+        Ok(_) => Ok(true),
+        Err(NftablesError::NftFailed { stderr, .. })
+            if stderr.contains("No such file or directory") =>
+        {
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
 }
 
 pub fn get_current_ruleset_raw(
     program: Option<&str>,
     args: Option<Vec<&str>>,
 ) -> Result<String, NftablesError> {
-    let mut nft_cmd = get_command(program);
+    list_raw(&mut get_command(program), &NftListOptions::default(), args)
+}
+
+/// Like [get_current_ruleset_raw], but returns nft's output as bytes without
+/// checking that it is valid UTF-8.
+///
+/// Useful to inspect malformed output that fails with [NftablesError::NftOutputEncoding].
+pub fn get_current_ruleset_bytes(
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+) -> Result<Vec<u8>, NftablesError> {
+    list_bytes(&mut get_command(program), &NftListOptions::default(), args)
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+/// Options for listing the ruleset, passed to nft as global flags.
+pub struct NftListOptions {
+    /// Omit the elements of sets and maps (`nft --terse`).
+    ///
+    /// Listed sets and maps will have no `elem` property, which makes
+    /// listing rulesets with huge sets considerably cheaper.
+    pub terse: bool,
+}
+
+impl NftListOptions {
+    fn flags(&self) -> Vec<&'static str> {
+        let mut flags = Vec::new();
+        if self.terse {
+            flags.push("-t");
+        }
+        flags
+    }
+}
+
+/// Like [get_current_ruleset], but with the given [NftListOptions].
+pub fn get_current_ruleset_with_options(
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+    options: &NftListOptions,
+) -> Result<Nftables, NftablesError> {
+    let output = list_raw(&mut get_command(program), options, args)?;
+    serde_json::from_str(&output).map_err(invalid_json)
+}
+
+/// Runs `nft -j [options] <args>`, listing the whole ruleset if no args are given.
+fn list_raw(
+    nft_cmd: &mut Command,
+    options: &NftListOptions,
+    args: Option<Vec<&str>>,
+) -> Result<String, NftablesError> {
+    let stdout = list_bytes(nft_cmd, options, args)?;
+    read_output(nft_cmd, stdout)
+}
+
+/// Like [list_raw], but returns stdout without decoding it.
+fn list_bytes(
+    nft_cmd: &mut Command,
+    options: &NftListOptions,
+    args: Option<Vec<&str>>,
+) -> Result<Vec<u8>, NftablesError> {
     let default_args = ["list", "ruleset"];
     let args = match &args {
         Some(args) => args.as_slice(),
         None => &default_args,
     };
     let program = nft_cmd.get_program().to_str().unwrap().to_string();
-    let process_result =
-        nft_cmd
-            .arg("-j")
-            .args(args)
-            .output()
-            .map_err(|e| NftablesError::NftExecution {
-                inner: e,
-                program: program.clone(),
-            })?;
-
-    let stdout = read_output(&nft_cmd, process_result.stdout)?;
+    debug!("running {} -j {:?} {:?}", program, options.flags(), args);
+    let process_result = nft_cmd
+        .arg("-j")
+        .args(options.flags())
+        .args(args)
+        .output()
+        .map_err(|e| NftablesError::NftExecution {
+            inner: e,
+            program: program.clone(),
+        })?;
 
     if !process_result.status.success() {
-        let stderr = read_output(&nft_cmd, process_result.stderr)?;
+        let stdout = read_output(nft_cmd, process_result.stdout)?;
+        let stderr = read_output(nft_cmd, process_result.stderr)?;
 
         return Err(NftablesError::NftFailed {
             program,
@@ -71,7 +238,7 @@ pub fn get_current_ruleset_raw(
             stderr,
         });
     }
-    Ok(stdout)
+    Ok(process_result.stdout)
 }
 
 pub fn apply_ruleset(
@@ -83,12 +250,175 @@ pub fn apply_ruleset(
     apply_ruleset_raw(nftables, program, args)
 }
 
+/// Like [apply_ruleset], but also writes the JSON payload passed to nft to `tee`.
+///
+/// The payload is written to `tee` before nft is run, so it is available
+/// for inspection even if applying the ruleset fails.
+pub fn apply_ruleset_tee(
+    nftables: &Nftables,
+    tee: &mut impl Write,
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+) -> Result<(), NftablesError> {
+    let payload = serde_json::to_string(nftables).expect("failed to serialize Nftables struct");
+    tee.write_all(payload.as_bytes())
+        .and_then(|_| tee.flush())
+        .map_err(NftablesError::NftTeeWrite)?;
+    apply_ruleset_raw(payload, program, args)
+}
+
+/// Like [apply_ruleset], but locates the failing object if nft rejects the ruleset.
+///
+/// nft does not report which object of a JSON ruleset it rejected.
+/// If applying fails, growing prefixes of the ruleset are therefore checked
+/// with `nft -c` (which does not change the ruleset) in a binary search
+/// for the first failing object. This takes about log2(n) additional nft runs.
+///
+/// On failure, an [NftablesError::NftBatchFailed] is returned if the failing
+/// object could be located, otherwise the original error.
+pub fn apply_ruleset_locating_failure(
+    nftables: &Nftables,
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+) -> Result<(), NftablesError> {
+    let err = match apply_ruleset(nftables, program, args.clone()) {
+        Err(err @ NftablesError::NftFailed { .. }) => err,
+        result => return result,
+    };
+    let prefix_applies = |len: usize| {
+        let prefix = Nftables {
+            objects: nftables.objects[..len].to_vec(),
+        };
+        let mut check_args = args.clone().unwrap_or_default();
+        check_args.push("-c");
+        apply_ruleset(&prefix, program, Some(check_args)).is_ok()
+    };
+    // Invariant: the first `lo` objects apply, the first `hi` objects do not.
+    let (mut lo, mut hi) = (0, nftables.objects.len());
+    if hi == 0 || prefix_applies(hi) {
+        return Err(err);
+    }
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if prefix_applies(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Err(NftablesError::NftBatchFailed {
+        index: lo,
+        object: Box::new(nftables.objects[lo].clone()),
+        inner: Box::new(err),
+    })
+}
+
+/// Computes a fingerprint of a listed ruleset, ignoring its metainfo.
+///
+/// nftables assigns new handles to objects on every change, so the fingerprint
+/// changes whenever the ruleset is modified. Flags are hashed in sorted order,
+/// so the order in which nft lists them does not matter.
+///
+/// The fingerprint is the 64-bit FNV-1a hash of the ruleset's JSON form. It does
+/// not depend on the Rust release, but may change with the serialization of this
+/// crate, so fingerprints should not be persisted across crate upgrades.
+pub fn ruleset_fingerprint(ruleset: &Nftables) -> u64 {
+    let ruleset = ruleset.clone().without_metainfo();
+    let mut json = serde_json::to_value(&ruleset).expect("failed to serialize Nftables struct");
+    sort_flags(&mut json);
+    fnv1a(json.to_string().as_bytes())
+}
+
+/// Sorts all `flags` arrays, which are serialized from `HashSet`s in arbitrary order.
+pub(crate) fn sort_flags(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if let ("flags", serde_json::Value::Array(flags)) = (key.as_str(), &mut *value) {
+                    flags.sort_by_key(|flag| flag.to_string());
+                }
+                sort_flags(value);
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(sort_flags),
+        _ => {}
+    }
+}
+
+/// 64-bit FNV-1a hash, which unlike `DefaultHasher` is stable across Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Lists the current ruleset and returns its [fingerprint](ruleset_fingerprint).
+pub fn get_current_ruleset_fingerprint(program: Option<&str>) -> Result<u64, NftablesError> {
+    get_current_ruleset(program, None).map(|ruleset| ruleset_fingerprint(&ruleset))
+}
+
+/// Like [apply_ruleset], but only applies `nftables` if the current ruleset
+/// still has the `baseline` [fingerprint](ruleset_fingerprint).
+///
+/// Returns [NftablesError::NftRulesetChanged] without applying anything
+/// if the fingerprints differ. nft offers no compare-and-swap, so changes
+/// made between listing and applying the ruleset are not detected.
+///
+/// The ruleset is listed with `args` followed by `list ruleset`, so global
+/// flags apply to both listing and applying.
+pub fn apply_if_unchanged(
+    nftables: &Nftables,
+    baseline: u64,
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+) -> Result<(), NftablesError> {
+    let list_args = args.clone().map(|mut args| {
+        args.extend(["list", "ruleset"]);
+        args
+    });
+    let found = ruleset_fingerprint(&get_current_ruleset(program, list_args)?);
+    if found != baseline {
+        return Err(NftablesError::NftRulesetChanged {
+            expected: baseline,
+            found,
+        });
+    }
+    apply_ruleset(nftables, program, args)
+}
+
+/// Like [apply_ruleset], but runs nft with `--echo --handle` and returns
+/// the echoed commands, which carry the handles assigned by the kernel.
+///
+/// See [Batch::apply_and_collect_handles](crate::batch::Batch::apply_and_collect_handles)
+/// to merge the handles into the applied objects.
+pub fn apply_ruleset_echo(
+    nftables: &Nftables,
+    program: Option<&str>,
+    args: Option<Vec<&str>>,
+) -> Result<Nftables, NftablesError> {
+    let nftables = serde_json::to_string(nftables).expect("failed to serialize Nftables struct");
+    let mut args = args.unwrap_or_default();
+    args.extend_from_slice(&["--echo", "--handle"]);
+    let output = apply_ruleset_output(&mut get_command(program), &nftables, Some(args))?;
+    serde_json::from_str(&output).map_err(invalid_json)
+}
+
 pub fn apply_ruleset_raw(
     payload: String,
     program: Option<&str>,
     args: Option<Vec<&str>>,
 ) -> Result<(), NftablesError> {
-    let mut nft_cmd = get_command(program);
+    apply_ruleset_output(&mut get_command(program), &payload, args).map(|_| ())
+}
+
+/// Pipes `payload` into `nft -j -f -` and returns nft's stdout.
+fn apply_ruleset_output(
+    nft_cmd: &mut Command,
+    payload: &str,
+    args: Option<Vec<&str>>,
+) -> Result<String, NftablesError> {
     let default_args = ["-j", "-f", "-"];
     let args: Vec<&str> = match args {
         Some(mut args) => {
@@ -98,6 +428,7 @@ pub fn apply_ruleset_raw(
         None => default_args.to_vec(),
     };
     let program = nft_cmd.get_program().to_str().unwrap().to_string();
+    debug!("running {} {:?} with payload {}", program, args, payload);
     let mut process = nft_cmd
         .args(args)
         .stdin(Stdio::piped())
@@ -119,10 +450,10 @@ pub fn apply_ruleset_raw(
 
     let result = process.wait_with_output();
     match result {
-        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) if output.status.success() => read_output(nft_cmd, output.stdout),
         Ok(process_result) => {
-            let stdout = read_output(&nft_cmd, process_result.stdout)?;
-            let stderr = read_output(&nft_cmd, process_result.stderr)?;
+            let stdout = read_output(nft_cmd, process_result.stdout)?;
+            let stderr = read_output(nft_cmd, process_result.stderr)?;
 
             Err(NftablesError::NftFailed {
                 program,
@@ -138,9 +469,361 @@ pub fn apply_ruleset_raw(
     }
 }
 
+/// Passes JSON payloads to nftables and reads back its JSON output.
+///
+/// [NftProcess] implements this by running the `nft` binary. Other
+/// implementations allow using the helpers without spawning processes,
+/// e.g. to test code that applies rulesets.
+pub trait RulesetTransport {
+    /// Applies a JSON payload, returning the JSON output of nftables (if any).
+    fn apply(&self, json: &str) -> Result<String, NftablesError>;
+
+    /// Lists ruleset elements as JSON.
+    ///
+    /// `args` selects what to list, e.g. `["list", "table", "ip", "filter"]`.
+    /// If not given, the whole ruleset is listed.
+    fn list(&self, args: Option<&[&str]>) -> Result<String, NftablesError>;
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+/// Communicates with nftables by running the `nft` binary.
+pub struct NftProcess {
+    /// Path to the nft binary; searched in `PATH` if not given.
+    pub program: Option<String>,
+    /// Hook run in the nft child process before it executes nft.
+    #[cfg(unix)]
+    pub pre_exec: Option<PreExecHook>,
+}
+
+impl NftProcess {
+    /// Creates a transport running the given nft binary, or `nft` from `PATH`.
+    pub fn new(program: Option<&str>) -> NftProcess {
+        NftProcess {
+            program: program.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    /// Sets a hook run in the nft child process before it executes nft.
+    #[cfg(unix)]
+    pub fn with_pre_exec(mut self, hook: PreExecHook) -> NftProcess {
+        self.pre_exec = Some(hook);
+        self
+    }
+
+    fn command(&self) -> Command {
+        #[allow(unused_mut)]
+        let mut nft_cmd = get_command(self.program.as_deref());
+        #[cfg(unix)]
+        if let Some(PreExecHook(hook)) = &self.pre_exec {
+            let hook = Arc::clone(hook);
+            // SAFETY: upheld by the caller of PreExecHook::new.
+            unsafe {
+                nft_cmd.pre_exec(move || hook());
+            }
+        }
+        nft_cmd
+    }
+}
+
+impl RulesetTransport for NftProcess {
+    fn apply(&self, json: &str) -> Result<String, NftablesError> {
+        apply_ruleset_output(&mut self.command(), json, None)
+    }
+
+    fn list(&self, args: Option<&[&str]>) -> Result<String, NftablesError> {
+        list_raw(
+            &mut self.command(),
+            &NftListOptions::default(),
+            args.map(<[&str]>::to_vec),
+        )
+    }
+}
+
+/// A closure run in the nft child process after `fork` and before `exec`,
+/// see [CommandExt::pre_exec].
+///
+/// It can be used to limit the resources of nft, e.g. by calling
+/// `setpriority(2)`, `ioprio_set(2)` or writing `/proc/self/oom_score_adj`
+/// to set its nice value, I/O priority or OOM score. If the hook returns an
+/// error, nft is not run and the error is returned as [NftablesError::NftExecution].
+///
+/// Hooks are compared by identity.
+#[cfg(unix)]
+#[derive(Clone)]
+pub struct PreExecHook(Arc<dyn Fn() -> io::Result<()> + Send + Sync>);
+
+#[cfg(unix)]
+impl PreExecHook {
+    /// Wraps a closure to run before executing nft.
+    ///
+    /// # Safety
+    ///
+    /// The closure runs in the forked child process and must uphold the
+    /// requirements of [CommandExt::pre_exec]: in particular, it may only
+    /// call async-signal-safe functions and must not allocate memory or
+    /// acquire locks.
+    pub unsafe fn new(hook: impl Fn() -> io::Result<()> + Send + Sync + 'static) -> PreExecHook {
+        PreExecHook(Arc::new(hook))
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Debug for PreExecHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PreExecHook(..)")
+    }
+}
+
+#[cfg(unix)]
+impl PartialEq for PreExecHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(unix)]
+impl Eq for PreExecHook {}
+
+/// Applies a ruleset through the given [transport](RulesetTransport).
+pub fn apply_ruleset_via(
+    transport: &impl RulesetTransport,
+    nftables: &Nftables,
+) -> Result<(), NftablesError> {
+    let nftables = serde_json::to_string(nftables).expect("failed to serialize Nftables struct");
+    transport.apply(&nftables).map(|_| ())
+}
+
+/// Reads the current ruleset through the given [transport](RulesetTransport).
+pub fn get_current_ruleset_via(
+    transport: &impl RulesetTransport,
+    args: Option<&[&str]>,
+) -> Result<Nftables, NftablesError> {
+    let output = transport.list(args)?;
+    serde_json::from_str(&output).map_err(invalid_json)
+}
+
+/// Applies a ruleset file in nft's native (non-JSON) syntax by running `nft -f <path>`.
+pub fn apply_native_file(
+    path: impl AsRef<Path>,
+    program: Option<&str>,
+) -> Result<(), NftablesError> {
+    apply_native_file_with_options(path, program, &NftNativeOptions::default())
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+/// Options for applying native ruleset files, passed to nft as global flags.
+pub struct NftNativeOptions {
+    /// Directories searched for files included by the ruleset (`nft -I <dir>`).
+    pub include_dirs: Vec<PathBuf>,
+    /// Variables defined for the ruleset (`nft -D <name>=<value>`).
+    ///
+    /// The ruleset refers to them as `$name`.
+    pub defines: Vec<(String, String)>,
+}
+
+impl NftNativeOptions {
+    fn flags(&self) -> Vec<OsString> {
+        let mut flags = Vec::new();
+        for dir in &self.include_dirs {
+            flags.push(OsString::from("-I"));
+            flags.push(dir.clone().into_os_string());
+        }
+        for (name, value) in &self.defines {
+            flags.push(OsString::from("-D"));
+            flags.push(OsString::from(format!("{}={}", name, value)));
+        }
+        flags
+    }
+}
+
+/// Like [apply_native_file], but with the given [NftNativeOptions].
+pub fn apply_native_file_with_options(
+    path: impl AsRef<Path>,
+    program: Option<&str>,
+    options: &NftNativeOptions,
+) -> Result<(), NftablesError> {
+    let mut nft_cmd = get_command(program);
+    let program = nft_cmd.get_program().to_str().unwrap().to_string();
+    debug!(
+        "running {} {:?} -f {}",
+        program,
+        options.flags(),
+        path.as_ref().display()
+    );
+    let output = nft_cmd
+        .args(options.flags())
+        .arg("-f")
+        .arg(path.as_ref())
+        .output()
+        .map_err(|e| NftablesError::NftExecution {
+            program: program.clone(),
+            inner: e,
+        })?;
+    if !output.status.success() {
+        let stdout = read_output(&nft_cmd, output.stdout)?;
+        let stderr = read_output(&nft_cmd, output.stderr)?;
+        return Err(NftablesError::NftFailed {
+            program,
+            hint: format!("applying native ruleset file {}", path.as_ref().display()),
+            stdout,
+            stderr,
+        });
+    }
+    Ok(())
+}
+
+/// Applies a ruleset file in nft's native syntax (see [apply_native_file]),
+/// then reads back the resulting ruleset as JSON.
+pub fn apply_native_file_and_list(
+    path: impl AsRef<Path>,
+    program: Option<&str>,
+) -> Result<Nftables, NftablesError> {
+    apply_native_file(path, program)?;
+    get_current_ruleset(program, None)
+}
+
+/// Spawns `nft -j monitor` and returns an iterator over the reported events.
+///
+/// The iterator blocks until nft reports the next event and ends when nft exits.
+/// If nft exits unsuccessfully, the last item is [NftablesError::NftFailed]
+/// holding nft's stderr.
+pub fn monitor(program: Option<&str>) -> Result<NftMonitor, NftablesError> {
+    let mut nft_cmd = get_command(program);
+    let program = nft_cmd.get_program().to_str().unwrap().to_string();
+    let mut child = nft_cmd
+        .args(["-j", "monitor"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| NftablesError::NftExecution {
+            program: program.clone(),
+            inner: e,
+        })?;
+    let reader = BufReader::new(child.stdout.take().unwrap());
+    Ok(NftMonitor {
+        program,
+        child,
+        reader,
+        exited: false,
+    })
+}
+
+/// Iterator over the [events](MonitorEvent) of a running `nft -j monitor` process.
+///
+/// The nft process is killed when the iterator is dropped.
+pub struct NftMonitor {
+    program: String,
+    child: Child,
+    reader: BufReader<ChildStdout>,
+    exited: bool,
+}
+
+impl NftMonitor {
+    /// Waits for nft after it closed stdout and reports an unsuccessful exit.
+    fn exit_status(&mut self) -> Option<Result<MonitorEvent, NftablesError>> {
+        if self.exited {
+            return None;
+        }
+        self.exited = true;
+        // nft only writes to stderr before exiting, so reading it to the end cannot block for long.
+        let mut stderr = Vec::new();
+        if let Some(mut pipe) = self.child.stderr.take() {
+            let _ = pipe.read_to_end(&mut stderr);
+        }
+        match self.child.wait() {
+            Ok(status) if status.success() => None,
+            Ok(_) => Some(Err(NftablesError::NftFailed {
+                program: self.program.clone(),
+                hint: "monitoring the ruleset".to_string(),
+                stdout: String::new(),
+                stderr: String::from_utf8_lossy(&stderr).into_owned(),
+            })),
+            Err(e) => Some(Err(NftablesError::NftExecution {
+                program: self.program.clone(),
+                inner: e,
+            })),
+        }
+    }
+}
+
+impl Iterator for NftMonitor {
+    type Item = Result<MonitorEvent, NftablesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            // read_line() buffers partial output until a full line (or EOF) is available.
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return self.exit_status(),
+                Ok(_) if line.trim().is_empty() => continue,
+                Ok(_) => return Some(serde_json::from_str(line.trim()).map_err(invalid_json)),
+                Err(e) => {
+                    return Some(Err(NftablesError::NftExecution {
+                        program: self.program.clone(),
+                        inner: e,
+                    }))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for NftMonitor {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Shortens `output` to at most [MAX_OUTPUT_DISPLAY_LEN] bytes, marking truncation with an ellipsis.
+fn truncate_output(output: &str) -> Cow<'_, str> {
+    if output.len() <= MAX_OUTPUT_DISPLAY_LEN {
+        return Cow::Borrowed(output);
+    }
+    let mut end = MAX_OUTPUT_DISPLAY_LEN;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!("{}…", &output[..end]))
+}
+
+/// Creates a command running the nft binary.
+///
+/// The binary is chosen in the following order:
+/// 1. the given `program`,
+/// 2. the path in the [`NFT_BIN`](NFT_BIN_ENV) environment variable,
+/// 3. `nft` if found in `PATH`,
+/// 4. the first existing one of [NFT_FALLBACK_PATHS],
+/// 5. `nft`, which will fail to execute.
 fn get_command(program: Option<&str>) -> Command {
-    let nft_executable: &str = program.unwrap_or(NFT_EXECUTABLE);
-    Command::new(nft_executable)
+    match program {
+        Some(program) => Command::new(program),
+        None => Command::new(discover_nft()),
+    }
+}
+
+fn discover_nft() -> OsString {
+    if let Some(program) = env::var_os(NFT_BIN_ENV).filter(|program| !program.is_empty()) {
+        return program;
+    }
+    let in_path = env::var_os("PATH").map_or(false, |path| {
+        env::split_paths(&path).any(|dir| dir.join(NFT_EXECUTABLE).is_file())
+    });
+    if !in_path {
+        if let Some(path) = NFT_FALLBACK_PATHS
+            .iter()
+            .find(|path| Path::new(path).is_file())
+        {
+            return OsString::from(path);
+        }
+    }
+    OsString::from(NFT_EXECUTABLE)
+}
+
+fn invalid_json(err: serde_json::Error) -> NftablesError {
+    NftablesError::NftParse(ParseError::InvalidJson(err))
 }
 
 fn read_output(cmd: &Command, bytes: Vec<u8>) -> Result<String, NftablesError> {
@@ -37,6 +37,18 @@ pub mod helper;
 /// Contains node visitors for serde.
 pub mod visitor;
 
+/// Contains the error type returned by validation checks.
+pub mod validation;
+
+/// Contains the error type returned when parsing nftables JSON documents.
+pub mod error;
+
+/// Contains parsing support for JSON output of older nftables releases.
+pub mod compat;
+
+/// Renders nftables documents in nft syntax, see [batch::Batch::explain].
+mod explain;
+
 // Default values for Default implementations.
 const DEFAULT_FAMILY: types::NfFamily = types::NfFamily::INet;
 const DEFAULT_TABLE: &str = "filter";
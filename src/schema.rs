@@ -1,11 +1,16 @@
 use std::collections::HashSet;
 
 use crate::{
-    expr::Expression, stmt::Statement, types::*, visitor::single_string_to_option_vec,
+    error::ParseError,
+    expr::{Expression, NamedExpression},
+    stmt::Statement,
+    types::*,
+    validation::ValidationError,
+    visitor::{single_string_to_option_hashset, single_string_to_option_vec},
     DEFAULT_CHAIN, DEFAULT_FAMILY, DEFAULT_TABLE,
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Serialize};
 
 use strum_macros::EnumString;
 
@@ -21,8 +26,79 @@ pub struct Nftables {
     pub objects: Vec<NfObject>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+impl Nftables {
+    /// Creates a descriptive document of the given list objects, without commands.
+    ///
+    /// This corresponds to the output format of `nft -j list ruleset`, which
+    /// makes it suitable as a desired-state snapshot to compare listed rulesets with.
+    pub fn from_objects(objects: impl IntoIterator<Item = NfListObject>) -> Nftables {
+        Nftables {
+            objects: objects
+                .into_iter()
+                .map(|obj| NfObject::ListObject(Box::new(obj)))
+                .collect(),
+        }
+    }
+
+    /// Removes all [metainfo objects](MetainfoObject).
+    ///
+    /// Listed rulesets start with a metainfo object, which nftables does not
+    /// accept as input. Stripping it allows reapplying a listed ruleset.
+    pub fn strip_metainfo(&mut self) {
+        self.objects.retain(|obj| {
+            !matches!(obj, NfObject::ListObject(obj) if matches!(**obj, NfListObject::MetainfoObject(_)))
+        });
+    }
+
+    /// Returns the document without [metainfo objects](MetainfoObject)
+    /// (see [Nftables::strip_metainfo]).
+    pub fn without_metainfo(mut self) -> Nftables {
+        self.strip_metainfo();
+        self
+    }
+}
+
+/// Parses JSON text in the [nftables document](Nftables) format.
+///
+/// Errors report the path to the invalid property.
+///
+/// ```
+/// use nftables::schema::Nftables;
+///
+/// let nftables: Nftables = r#"{"nftables": [{"table": {"family": "ip", "name": "filter"}}]}"#
+///     .parse()
+///     .unwrap();
+/// assert_eq!(1, nftables.objects.len());
+///
+/// let err = r#"{"nftables": [{"table": {"family": "ipx", "name": "filter"}}]}"#
+///     .parse::<Nftables>()
+///     .unwrap_err();
+/// assert!(err.to_string().contains("nftables[0]"));
+/// ```
+impl std::str::FromStr for Nftables {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let deserializer = &mut serde_json::Deserializer::from_str(s);
+        serde_path_to_error::deserialize(deserializer).map_err(|err| ParseError::InvalidJsonAt {
+            path: err.path().to_string(),
+            inner: err.into_inner(),
+        })
+    }
+}
+
+/// Parses a JSON value in the [nftables document](Nftables) format.
+impl TryFrom<serde_json::Value> for Nftables {
+    type Error = ParseError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(ParseError::InvalidJson)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 /// A [ruleset element](NfListObject) or [command](NfCmd) in an [nftables document](Nftables).
 pub enum NfObject {
     /// A command.
@@ -31,9 +107,118 @@ pub enum NfObject {
     ListObject(Box<NfListObject>),
 }
 
+/// Property names identifying a [command](NfCmd).
+const CMD_NAMES: &[&str] = &[
+    "add", "replace", "create", "insert", "delete", "list", "reset", "flush", "rename",
+];
+
+/// Decides between command and ruleset element by the object's property name,
+/// so that errors in the enclosed object (e.g. a [rule](Rule) without `expr`)
+/// are reported as such instead of as a mismatch of all variants.
+///
+/// The object is deserialized in place, without buffering it.
+impl<'de> Deserialize<'de> for NfObject {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_map(NfObjectVisitor)
+    }
+}
+
+struct NfObjectVisitor;
+
+impl<'de> de::Visitor<'de> for NfObjectVisitor {
+    type Value = NfObject;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a command or ruleset element")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let name: String = match map.next_key()? {
+            Some(name) => name,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        let is_cmd = CMD_NAMES.contains(&name.as_str());
+        let object = de::value::MapAccessDeserializer::new(PeekedMap {
+            name: Some(name),
+            map,
+        });
+        if is_cmd {
+            NfCmd::deserialize(object).map(NfObject::CmdObject)
+        } else {
+            NfListObject::deserialize(object).map(|obj| NfObject::ListObject(Box::new(obj)))
+        }
+    }
+}
+
+/// A map whose first key was already read, which is handed out again before the remaining entries.
+struct PeekedMap<A> {
+    name: Option<String>,
+    map: A,
+}
+
+impl<'de, A> de::MapAccess<'de> for PeekedMap<A>
+where
+    A: de::MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.name.take() {
+            Some(name) => seed
+                .deserialize(de::value::StringDeserializer::new(name))
+                .map(Some),
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(seed)
+    }
+}
+
+impl NfObject {
+    /// Returns the ruleset element if this is a [list object](NfObject::ListObject).
+    pub fn as_list_object(&self) -> Option<&NfListObject> {
+        match self {
+            NfObject::ListObject(obj) => Some(obj),
+            NfObject::CmdObject(_) => None,
+        }
+    }
+
+    /// Returns the command if this is a [command object](NfObject::CmdObject).
+    pub fn as_cmd(&self) -> Option<&NfCmd> {
+        match self {
+            NfObject::CmdObject(cmd) => Some(cmd),
+            NfObject::ListObject(_) => None,
+        }
+    }
+
+    /// Returns `true` if this is a [command object](NfObject::CmdObject).
+    pub fn is_command(&self) -> bool {
+        matches!(self, NfObject::CmdObject(_))
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 /// A ruleset element in an [nftables document](Nftables).
+///
+/// Ruleset elements of newer nftables releases may be added in minor releases,
+/// so matches on this enum need a wildcard arm. To pick out a certain kind of
+/// element, the `as_*` accessors such as [NfListObject::as_rule] can be used instead.
 pub enum NfListObject {
     /// A table element.
     Table(Table),
@@ -70,6 +255,109 @@ pub enum NfListObject {
     SynProxy(SynProxy),
 }
 
+impl NfListObject {
+    /// Returns the table if this is a [table element](NfListObject::Table).
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            NfListObject::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+
+    /// Returns the chain if this is a [chain element](NfListObject::Chain).
+    pub fn as_chain(&self) -> Option<&Chain> {
+        match self {
+            NfListObject::Chain(chain) => Some(chain),
+            _ => None,
+        }
+    }
+
+    /// Returns the rule if this is a [rule element](NfListObject::Rule).
+    pub fn as_rule(&self) -> Option<&Rule> {
+        match self {
+            NfListObject::Rule(rule) => Some(rule),
+            _ => None,
+        }
+    }
+
+    /// Returns the set if this is a [set element](NfListObject::Set).
+    pub fn as_set(&self) -> Option<&Set> {
+        match self {
+            NfListObject::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+
+    /// Returns the map if this is a [map element](NfListObject::Map).
+    pub fn as_map(&self) -> Option<&Map> {
+        match self {
+            NfListObject::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this is an [element manipulation](NfListObject::Element).
+    pub fn as_element(&self) -> Option<&Element> {
+        match self {
+            NfListObject::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    /// Returns the handle of the ruleset element, if it has one.
+    ///
+    /// Elements and metainfo objects never have a handle.
+    pub fn handle(&self) -> Option<u32> {
+        match self {
+            NfListObject::Table(o) => o.handle,
+            NfListObject::Chain(o) => o.handle,
+            NfListObject::Rule(o) => o.handle,
+            NfListObject::Set(o) => o.handle,
+            NfListObject::Map(o) => o.handle,
+            NfListObject::FlowTable(o) => o.handle,
+            NfListObject::Counter(o) => o.handle,
+            NfListObject::Quota(o) => o.handle,
+            NfListObject::CTHelper(o) => o.handle,
+            NfListObject::Limit(o) => o.handle,
+            NfListObject::CTTimeout(o) => o.handle,
+            NfListObject::CTExpectation(o) => o.handle,
+            NfListObject::SynProxy(o) => o.handle,
+            NfListObject::Element(_) | NfListObject::MetainfoObject(_) => None,
+        }
+    }
+
+    /// Sets the handle of the ruleset element.
+    ///
+    /// Does nothing for elements and metainfo objects, which have no handle.
+    pub fn set_handle(&mut self, handle: Option<u32>) {
+        match self {
+            NfListObject::Table(o) => o.handle = handle,
+            NfListObject::Chain(o) => o.handle = handle,
+            NfListObject::Rule(o) => o.handle = handle,
+            NfListObject::Set(o) => o.handle = handle,
+            NfListObject::Map(o) => o.handle = handle,
+            NfListObject::FlowTable(o) => o.handle = handle,
+            NfListObject::Counter(o) => o.handle = handle,
+            NfListObject::Quota(o) => o.handle = handle,
+            NfListObject::CTHelper(o) => o.handle = handle,
+            NfListObject::Limit(o) => o.handle = handle,
+            NfListObject::CTTimeout(o) => o.handle = handle,
+            NfListObject::CTExpectation(o) => o.handle = handle,
+            NfListObject::SynProxy(o) => o.handle = handle,
+            NfListObject::Element(_) | NfListObject::MetainfoObject(_) => {}
+        }
+    }
+}
+
+/// Parses a JSON value describing a single ruleset element, e.g. `{"table": {...}}`.
+impl TryFrom<serde_json::Value> for NfListObject {
+    type Error = ParseError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(ParseError::InvalidJson)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// A command is an object with a single property whose name identifies the command.
@@ -117,6 +405,20 @@ pub enum NfCmd {
     Rename(Chain),
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+/// An event reported by `nft -j monitor`.
+///
+/// Each line of the monitor output is a single JSON object describing
+/// a change to the ruleset.
+pub enum MonitorEvent {
+    /// A ruleset element was added.
+    Add(NfListObject),
+    /// A ruleset element was deleted.
+    Delete(NfListObject),
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Reset state in suitable objects, i.e. zero their internal counter.
@@ -129,6 +431,14 @@ pub enum ResetObject {
     Quota(Quota),
     /// A list of quotas to reset.
     Quotas(Vec<Quota>),
+    /// A rule whose stateful statements (e.g. counters) to reset.
+    Rule(Rule),
+    /// Set elements whose stateful expressions (e.g. counters) to reset.
+    Element(Element),
+    /// A set whose elements' stateful expressions (e.g. counters) to reset.
+    Set(Set),
+    /// A map whose elements' stateful expressions (e.g. counters) to reset.
+    Map(Map),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -151,7 +461,13 @@ pub enum FlushObject {
 
 // Ruleset Elements
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+/// Identifies a [table](Table) by family and name, e.g. to key a `HashMap`.
+pub type TableIdentity = (NfFamily, String);
+
+/// Identifies a [chain](Chain), [set](Set) or [map](Map) by family, table and name.
+pub type ObjectIdentity = (NfFamily, String, String);
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 /// This object describes a table.
 pub struct Table {
     /// The table’s [family](NfFamily), e.g. "ip" or "ip6".
@@ -177,7 +493,14 @@ impl Default for Table {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+impl Table {
+    /// Returns the family and name identifying the table.
+    pub fn identity(&self) -> TableIdentity {
+        (self.family, self.name.clone())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 /// This object describes a chain.
 pub struct Chain {
     /// The table’s family.
@@ -211,12 +534,19 @@ pub struct Chain {
     ///
     /// (Base chains): <https://wiki.nftables.org/wiki-nftables/index.php/Configuring_chains#Adding_base_chains>
     pub prio: Option<i32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    /// The chain’s bound interface (if in the netdev family).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "single_string_to_option_vec"
+    )]
+    /// The chain’s bound interfaces (if in the netdev family).
     /// Required for [base chains](Base chains).
     ///
+    /// Serialized as an array, i.e. `devices = { ... }` in nft syntax.
+    /// nft lists chains bound to a single interface with a plain string.
+    ///
     /// (Base chains): <https://wiki.nftables.org/wiki-nftables/index.php/Configuring_chains#Adding_base_chains>
-    pub dev: Option<String>,
+    pub dev: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// The chain’s [policy](NfChainPolicy).
     /// Required for [base chains](Base chains).
@@ -243,6 +573,28 @@ impl Default for Chain {
     }
 }
 
+impl Chain {
+    /// Returns the family, table and name identifying the chain.
+    pub fn identity(&self) -> ObjectIdentity {
+        (self.family, self.table.clone(), self.name.clone())
+    }
+
+    /// Returns `true` if this is a base chain, i.e. a chain attached to a hook.
+    pub fn is_base(&self) -> bool {
+        self._type.is_some() || self.hook.is_some()
+    }
+
+    /// Checks the chain for invalid combinations of properties.
+    ///
+    /// Base chains are required to state their policy explicitly.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.is_base() && self.policy.is_none() {
+            return Err(ValidationError::MissingChainPolicy(self.name.clone()));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 /// This object describes a rule.
 ///
@@ -290,6 +642,32 @@ impl Default for Rule {
     }
 }
 
+impl Rule {
+    /// Creates a rule in the given chain consisting of the given statements.
+    pub fn new(
+        family: NfFamily,
+        table: impl Into<String>,
+        chain: impl Into<String>,
+        expr: impl IntoIterator<Item = Statement>,
+    ) -> Rule {
+        Rule {
+            family,
+            table: table.into(),
+            chain: chain.into(),
+            expr: expr.into_iter().collect(),
+            ..Rule::default()
+        }
+    }
+
+    /// Checks the rule's comment and all statements in the context of the rule's family.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate_comment(&self.comment)?;
+        self.expr
+            .iter()
+            .try_for_each(|stmt| stmt.validate(self.family))
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 /// Named set that holds expression elements.
 pub struct Set {
@@ -325,6 +703,11 @@ pub struct Set {
     #[serde(rename = "gc-interval", skip_serializing_if = "Option::is_none")]
     /// Garbage collector interval in seconds.
     pub gc_interval: Option<u32>,
+    #[serde(rename = "auto-merge", skip_serializing_if = "Option::is_none")]
+    /// Merge adjacent and overlapping elements on insertion.
+    ///
+    /// Only supported for sets with the [interval flag](SetFlag::Interval).
+    pub auto_merge: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Maximum number of elements supported.
     pub size: Option<u32>,
@@ -349,12 +732,42 @@ impl Default for Set {
             elem: None,
             timeout: None,
             gc_interval: None,
+            auto_merge: None,
             size: None,
             comment: None,
         }
     }
 }
 
+impl Set {
+    /// Returns the family, table and name identifying the set.
+    pub fn identity(&self) -> ObjectIdentity {
+        (self.family, self.table.clone(), self.name.clone())
+    }
+
+    /// Returns the number of elements, i.e. the length of `elem`.
+    ///
+    /// Sets listed with [terse](crate::helper::NftListOptions::terse) output have no elements.
+    pub fn element_count(&self) -> usize {
+        self.elem.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Returns `true` if the set holds as many elements as its `size` allows.
+    ///
+    /// Sets without `size` are never full.
+    pub fn is_full(&self) -> bool {
+        self.size
+            .map_or(false, |size| self.element_count() >= size as usize)
+    }
+
+    /// Checks the set for invalid combinations of properties.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.set_type.validate()?;
+        validate_comment(&self.comment)?;
+        validate_auto_merge(&self.name, self.auto_merge, &self.flags)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 /// Named map that holds expression elements.
 /// Maps are a special form of sets in that they translate a unique key to a value.
@@ -394,6 +807,11 @@ pub struct Map {
     #[serde(rename = "gc-interval", skip_serializing_if = "Option::is_none")]
     /// Garbage collector interval in seconds.
     pub gc_interval: Option<u32>,
+    #[serde(rename = "auto-merge", skip_serializing_if = "Option::is_none")]
+    /// Merge adjacent and overlapping elements on insertion.
+    ///
+    /// Only supported for maps with the [interval flag](SetFlag::Interval).
+    pub auto_merge: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Maximum number of elements supported.
     pub size: Option<u32>,
@@ -419,12 +837,77 @@ impl Default for Map {
             elem: None,
             timeout: None,
             gc_interval: None,
+            auto_merge: None,
             size: None,
             comment: None,
         }
     }
 }
 
+impl Map {
+    /// Returns the family, table and name identifying the map.
+    pub fn identity(&self) -> ObjectIdentity {
+        (self.family, self.table.clone(), self.name.clone())
+    }
+
+    /// Returns the number of elements, i.e. the length of `elem`.
+    ///
+    /// Maps listed with [terse](crate::helper::NftListOptions::terse) output have no elements.
+    pub fn element_count(&self) -> usize {
+        self.elem.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Returns `true` if the map holds as many elements as its `size` allows.
+    ///
+    /// Maps without `size` are never full.
+    pub fn is_full(&self) -> bool {
+        self.size
+            .map_or(false, |size| self.element_count() >= size as usize)
+    }
+
+    /// Checks the map for invalid combinations of properties.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.set_type.validate()?;
+        self.map.validate()?;
+        validate_comment(&self.comment)?;
+        validate_auto_merge(&self.name, self.auto_merge, &self.flags)
+    }
+}
+
+/// Maximum length of comments in bytes (`NFTNL_UDATA_COMMENT_MAXLEN`).
+pub const MAX_COMMENT_LEN: usize = 128;
+
+/// Checks that a comment fits nftables' length limit and contains
+/// no quotes or line breaks, which nft cannot represent.
+fn validate_comment(comment: &Option<String>) -> Result<(), ValidationError> {
+    let comment = match comment {
+        Some(comment) => comment,
+        None => return Ok(()),
+    };
+    if comment.len() > MAX_COMMENT_LEN {
+        return Err(ValidationError::CommentTooLong(comment.len()));
+    }
+    match comment.chars().find(|c| matches!(c, '"' | '\n' | '\r')) {
+        Some(c) => Err(ValidationError::InvalidCommentCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+/// Checks that `auto-merge` is only enabled for interval sets and maps.
+fn validate_auto_merge(
+    name: &str,
+    auto_merge: Option<bool>,
+    flags: &Option<HashSet<SetFlag>>,
+) -> Result<(), ValidationError> {
+    let interval = flags
+        .as_ref()
+        .map_or(false, |flags| flags.contains(&SetFlag::Interval));
+    if auto_merge == Some(true) && !interval {
+        return Err(ValidationError::AutoMergeWithoutInterval(name.to_string()));
+    }
+    Ok(())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 /// Wrapper for single or concatenated set types.
@@ -436,9 +919,35 @@ pub enum SetTypeValue {
     Concatenated(Vec<SetType>),
 }
 
+impl SetTypeValue {
+    /// Returns the number of components of this set type.
+    pub fn arity(&self) -> usize {
+        match self {
+            SetTypeValue::Single(_) => 1,
+            SetTypeValue::Concatenated(types) => types.len(),
+        }
+    }
+
+    /// Checks that a concatenated set type consists of at least two components.
+    ///
+    /// A single type must be given as [SetTypeValue::Single] instead of a
+    /// one-element concatenation.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            SetTypeValue::Concatenated(types) if types.len() < 2 => {
+                Err(ValidationError::InvalidConcatenation(types.len()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, EnumString)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 /// Describes a set’s datatype.
+///
+/// Further datatypes may be added in minor releases.
 pub enum SetType {
     #[serde(rename = "ipv4_addr")]
     #[strum(serialize = "ipv4_addr")]
@@ -468,6 +977,10 @@ pub enum SetType {
     #[strum(serialize = "ifname")]
     /// Network interface name (eth0, eth1..).
     Ifname,
+    #[serde(rename = "verdict")]
+    #[strum(serialize = "verdict")]
+    /// Verdict, used as value type of verdict maps.
+    Verdict,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -532,6 +1045,71 @@ impl Default for Element {
     }
 }
 
+impl Element {
+    /// Creates elements of the named map, mapping each key to its value.
+    ///
+    /// Values may be [verdicts](crate::expr::Verdict) to populate a verdict map,
+    /// e.g. `{ 80 : jump http }`. See [Expression::map_element].
+    pub fn mapping<K, V>(
+        family: NfFamily,
+        table: impl Into<String>,
+        name: impl Into<String>,
+        mappings: impl IntoIterator<Item = (K, V)>,
+    ) -> Element
+    where
+        K: Into<Expression>,
+        V: Into<Expression>,
+    {
+        Element {
+            family,
+            table: table.into(),
+            name: name.into(),
+            elem: mappings
+                .into_iter()
+                .map(|(key, value)| Expression::map_element(key, value))
+                .collect(),
+        }
+    }
+
+    /// Checks that the elements can be added to the given set.
+    ///
+    /// Ranges and prefixes can only be added to sets with the
+    /// [interval flag](SetFlag::Interval), and concatenated elements need
+    /// as many components as the [set type](SetTypeValue::arity).
+    pub fn validate_against(&self, set: &Set) -> Result<(), ValidationError> {
+        let interval_set = set
+            .flags
+            .as_ref()
+            .map_or(false, |flags| flags.contains(&SetFlag::Interval));
+        if !interval_set && self.elem.iter().any(is_interval) {
+            return Err(ValidationError::IntervalInPlainSet(set.name.clone()));
+        }
+        let expected = set.set_type.arity();
+        if let Some(found) = self
+            .elem
+            .iter()
+            .map(Expression::arity)
+            .find(|arity| *arity != expected)
+        {
+            return Err(ValidationError::ArityMismatch {
+                set: set.name.clone(),
+                expected,
+                found,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if the element expression is a range or prefix.
+fn is_interval(expr: &Expression) -> bool {
+    match expr {
+        Expression::Range(_) | Expression::Named(NamedExpression::Prefix(_)) => true,
+        Expression::Named(NamedExpression::Elem(elem)) => is_interval(&elem.val),
+        _ => false,
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 /// [Flowtables] allow you to accelerate packet forwarding in software (and in hardware if your NIC supports it)
 /// by using a conntrack-based network stack bypass.
@@ -547,11 +1125,13 @@ pub struct FlowTable {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// The flow table’s handle. In input, it is used by the [delete command](NfCmd::Delete) only.
     pub handle: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The flow table’s [hook](NfHook).
     pub hook: Option<NfHook>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The flow table's *priority* can be a signed integer or *filter* which stands for 0.
     /// Addition and subtraction can be used to set relative priority, e.g., filter + 5 is equal to 5.
-    pub prio: Option<u32>,
+    pub prio: Option<i32>,
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -562,6 +1142,9 @@ pub struct FlowTable {
     /// Devices are required for both traffic directions.
     /// Vec of device names, e.g. `vec!["wg0".to_string(), "wg0".to_string()]`.
     pub dev: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    /// The flow table’s [flags](NfFlowTableFlag).
+    pub flags: Option<HashSet<NfFlowTableFlag>>,
 }
 
 /// Default [flowtable](FlowTable) named "myflowtable".
@@ -575,10 +1158,94 @@ impl Default for FlowTable {
             hook: None,
             prio: None,
             dev: None,
+            flags: None,
         }
     }
 }
 
+impl FlowTable {
+    /// Creates a flow table with only its identifying fields set,
+    /// e.g. for the [delete command](NfCmd::Delete).
+    pub fn reference(
+        family: NfFamily,
+        table: impl Into<String>,
+        name: impl Into<String>,
+    ) -> FlowTable {
+        FlowTable {
+            family,
+            table: table.into(),
+            name: name.into(),
+            ..FlowTable::default()
+        }
+    }
+}
+
+/// Builds a [flow table](FlowTable) attached to the `ingress` hook.
+///
+/// The priority defaults to [filter](NfPriority::Filter).
+pub struct FlowTableBuilder {
+    flowtable: FlowTable,
+}
+
+impl FlowTableBuilder {
+    /// Starts building a flow table with the given name in the given table.
+    pub fn new(
+        family: NfFamily,
+        table: impl Into<String>,
+        name: impl Into<String>,
+    ) -> FlowTableBuilder {
+        FlowTableBuilder {
+            flowtable: FlowTable {
+                family,
+                table: table.into(),
+                name: name.into(),
+                hook: Some(NfHook::Ingress),
+                prio: Some(NfPriority::Filter.value()),
+                ..FlowTable::default()
+            },
+        }
+    }
+
+    /// Sets the numeric priority.
+    pub fn prio(mut self, prio: i32) -> Self {
+        self.flowtable.prio = Some(prio);
+        self
+    }
+
+    /// Sets the priority from a named priority.
+    pub fn named_prio(self, prio: NfPriority) -> Self {
+        self.prio(prio.value())
+    }
+
+    /// Adds a device whose traffic is offloaded.
+    pub fn device(mut self, dev: impl Into<String>) -> Self {
+        self.flowtable
+            .dev
+            .get_or_insert_with(Vec::new)
+            .push(dev.into());
+        self
+    }
+
+    /// Adds a flag.
+    pub fn flag(mut self, flag: NfFlowTableFlag) -> Self {
+        self.flowtable
+            .flags
+            .get_or_insert_with(HashSet::new)
+            .insert(flag);
+        self
+    }
+
+    /// Enables counters for offloaded flows.
+    pub fn counter(self) -> Self {
+        self.flag(NfFlowTableFlag::Counter)
+    }
+
+    /// Returns the built flow table.
+    pub fn build(self) -> FlowTable {
+        self.flowtable
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 /// This object represents a named [counter].
 ///
@@ -851,6 +1518,15 @@ pub struct CTTimeout {
     pub l3proto: Option<String>,
 }
 
+impl CTTimeout {
+    /// Returns a statement assigning this timeout policy, i.e. `ct timeout set "<name>"`.
+    ///
+    /// The statement has to be used in a rule of the same table.
+    pub fn to_statement(&self) -> Statement {
+        Statement::ct_timeout(self.name.clone())
+    }
+}
+
 /// Default [ct timeout](CTTimeout) named "mycttimeout"
 impl Default for CTTimeout {
     fn default() -> Self {
@@ -898,6 +1574,15 @@ pub struct CTExpectation {
     pub size: Option<u32>,
 }
 
+impl CTExpectation {
+    /// Returns a statement assigning this expectation, i.e. `ct expectation set "<name>"`.
+    ///
+    /// The statement has to be used in a rule of the same table.
+    pub fn to_statement(&self) -> Statement {
+        Statement::ct_expectation(self.name.clone())
+    }
+}
+
 /// [SynProxy] intercepts new TCP connections and handles the initial 3-way handshake using
 /// syncookies instead of conntrack to establish the connection.
 ///
@@ -921,7 +1606,45 @@ pub struct SynProxy {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// The window scale (must match your backend server).
     pub wscale: Option<u8>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "single_string_to_option_hashset"
+    )]
     /// The synproxy's [flags](crate::types::SynProxyFlag).
     pub flags: Option<HashSet<SynProxyFlag>>,
 }
+
+impl SynProxy {
+    /// Creates a named synproxy with the given maximum segment size and window scale.
+    pub fn new(
+        family: NfFamily,
+        table: impl Into<String>,
+        name: impl Into<String>,
+        mss: u16,
+        wscale: u8,
+    ) -> SynProxy {
+        SynProxy {
+            family,
+            table: table.into(),
+            name: name.into(),
+            handle: None,
+            mss: Some(mss),
+            wscale: Some(wscale),
+            flags: None,
+        }
+    }
+
+    /// Checks that `mss` and `wscale` are either both set or both unset.
+    ///
+    /// Both have to match the backend server, so setting only one of them is
+    /// most likely a mistake.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        match (self.mss, self.wscale) {
+            (Some(_), None) | (None, Some(_)) => {
+                Err(ValidationError::IncompleteSynProxy(self.name.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,13 +1,20 @@
 use std::collections::HashSet;
+use std::net::SocketAddr;
 
 use serde::{Deserialize, Serialize};
 
 use strum_macros::EnumString;
 
-use crate::types::{RejectCode, SynProxyFlag};
-use crate::visitor::single_string_to_option_hashset_logflag;
+use crate::error::ParseError;
+use crate::types::{NfFamily, NfTimeUnit, RejectCode, SynProxyFlag};
+use crate::validation::ValidationError;
+use crate::visitor::{
+    serialize_none, single_string_to_option_hashset, single_string_to_option_hashset_logflag,
+};
 
-use crate::expr::Expression;
+use crate::expr::{
+    CTKey, CTStatus, Expression, Meta, MetaKey, NamedExpression, SetItem, Socket, CT,
+};
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -43,6 +50,7 @@ pub enum Statement {
     Flow(Flow),
     FWD(Option<FWD>),
     /// Disable connection tracking for the packet.
+    #[serde(serialize_with = "serialize_none")]
     Notrack,
     Dup(Dup),
     SNAT(Option<NAT>),
@@ -51,7 +59,8 @@ pub enum Statement {
     Redirect(Option<NAT>),   // redirect is subset of NAT options
     Reject(Option<Reject>),
     Set(Set),
-    // TODO: map
+    /// Add or update an element of a named map.
+    Map(MapUpdate),
     Log(Option<Log>),
 
     #[serde(rename = "ct helper")]
@@ -65,14 +74,23 @@ pub enum Statement {
     VerdictMap(VerdictMap),
 
     #[serde(rename = "ct count")]
+    /// Limit the number of connections (anonymous only, see [CTCount]).
     CTCount(CTCount),
 
     #[serde(rename = "ct timeout")]
     /// Assign connection tracking timeout policy.
+    ///
+    /// nftables only supports referencing a named
+    /// [ct timeout object](crate::schema::CTTimeout) here,
+    /// see [Statement::ct_timeout].
     CTTimeout(Expression), // CT timeout reference.
 
     #[serde(rename = "ct expectation")]
     /// Assign connection tracking expectation.
+    ///
+    /// nftables only supports referencing a named
+    /// [ct expectation object](crate::schema::CTExpectation) here,
+    /// see [Statement::ct_expectation].
     CTExpectation(Expression), // CT expectation reference.
 
     /// This represents an xt statement from xtables compat interface.
@@ -86,6 +104,89 @@ pub enum Statement {
     // TODO: secmark
 }
 
+/// Parses a JSON value describing a single statement, e.g. `{"accept": null}`.
+impl TryFrom<serde_json::Value> for Statement {
+    type Error = ParseError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(ParseError::InvalidJson)
+    }
+}
+
+impl Statement {
+    /// Assigns the named [ct timeout policy](crate::schema::CTTimeout),
+    /// i.e. `ct timeout set "<name>"`.
+    pub fn ct_timeout(name: impl Into<String>) -> Statement {
+        Statement::CTTimeout(Expression::String(name.into()))
+    }
+
+    /// Assigns the named [ct expectation object](crate::schema::CTExpectation),
+    /// i.e. `ct expectation set "<name>"`.
+    pub fn ct_expectation(name: impl Into<String>) -> Statement {
+        Statement::CTExpectation(Expression::String(name.into()))
+    }
+
+    /// Counts and drops packets, i.e. `counter drop`.
+    ///
+    /// Returns an anonymous [counter](Counter) followed by the verdict,
+    /// to be inlined into a rule's statements.
+    pub fn counter_drop() -> [Statement; 2] {
+        [
+            Statement::Counter(Counter::Anonymous(None)),
+            Statement::Drop(None),
+        ]
+    }
+
+    /// Counts and accepts packets, i.e. `counter accept`.
+    ///
+    /// Returns an anonymous [counter](Counter) followed by the verdict,
+    /// to be inlined into a rule's statements.
+    pub fn counter_accept() -> [Statement; 2] {
+        [
+            Statement::Counter(Counter::Anonymous(None)),
+            Statement::Accept(None),
+        ]
+    }
+
+    /// Logs and drops packets, i.e. `log prefix "<prefix>" drop`.
+    ///
+    /// Returns a [log statement](Log) followed by the verdict,
+    /// to be inlined into a rule's statements.
+    pub fn log_drop(prefix: impl Into<String>) -> [Statement; 2] {
+        [Statement::log_prefix(prefix), Statement::Drop(None)]
+    }
+
+    /// Logs and rejects packets, i.e. `log prefix "<prefix>" reject [with ...]`.
+    ///
+    /// Returns a [log statement](Log) followed by the [reject statement](Reject),
+    /// to be inlined into a rule's statements. If `reject` is `None`, nftables
+    /// picks the reject type.
+    pub fn log_reject(prefix: impl Into<String>, reject: Option<Reject>) -> [Statement; 2] {
+        [Statement::log_prefix(prefix), Statement::Reject(reject)]
+    }
+
+    fn log_prefix(prefix: impl Into<String>) -> Statement {
+        Statement::Log(Some(Log {
+            prefix: Some(prefix.into()),
+            ..Log::new(None)
+        }))
+    }
+
+    /// Checks the statement for invalid combinations of properties.
+    ///
+    /// `family` is the family of the table containing the rule, as some
+    /// statements have different requirements depending on it.
+    pub fn validate(&self, family: NfFamily) -> Result<(), ValidationError> {
+        match self {
+            Statement::SNAT(Some(nat)) | Statement::DNAT(Some(nat)) => nat.validate(family),
+            Statement::Log(Some(log)) => log.validate(),
+            Statement::Queue(queue) => queue.validate(),
+            Statement::Limit(limit) => limit.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// `accept` verdict.
 pub struct Accept {}
@@ -121,9 +222,110 @@ pub struct Match {
     pub op: Operator,
 }
 
+impl Match {
+    /// Matches connections having all of the given [conntrack status flags](CTStatus),
+    /// e.g. `ct status dnat`.
+    pub fn ct_status(flags: impl IntoIterator<Item = CTStatus>) -> Match {
+        Match {
+            left: Expression::Named(NamedExpression::CT(CT::new(CTKey::Status))),
+            right: CTStatus::to_expression(flags),
+            op: Operator::IN,
+        }
+    }
+
+    /// Matches connections carrying the given conntrack label, e.g. `ct label foo`.
+    ///
+    /// The label name has to be defined in nftables' `connlabel.conf`.
+    pub fn ct_label(label: impl Into<String>) -> Match {
+        Match {
+            left: Expression::Named(NamedExpression::CT(CT::new(CTKey::Label))),
+            right: Expression::String(label.into()),
+            op: Operator::EQ,
+        }
+    }
+
+    /// Compares the remaining lifetime of the connection's conntrack entry,
+    /// e.g. `ct expiration < 30s`.
+    pub fn ct_expiration(op: Operator, value: u64, unit: NfTimeUnit) -> Match {
+        Match {
+            left: Expression::Named(NamedExpression::CT(CT::new(CTKey::Expiration))),
+            right: Expression::time(value, unit),
+            op,
+        }
+    }
+
+    /// Matches packets of sockets in the cgroup v1 with the given net_cls classid,
+    /// e.g. `meta cgroup 0x00100001`.
+    pub fn meta_cgroup(classid: u32) -> Match {
+        Match {
+            left: Expression::Named(NamedExpression::Meta(Meta {
+                key: MetaKey::Cgroup,
+            })),
+            right: Expression::Number(classid.into()),
+            op: Operator::EQ,
+        }
+    }
+
+    /// Matches packets of sockets in the given cgroup v2 or its descendants,
+    /// e.g. `socket cgroupv2 level 2 "system.slice/sshd.service"`.
+    ///
+    /// `path` is relative to the cgroup v2 root. The level is the number of its components.
+    pub fn socket_cgroupv2(path: impl Into<String>) -> Match {
+        let path = path.into();
+        let level = path.split('/').filter(|c| !c.is_empty()).count() as u32;
+        Match {
+            left: Expression::Named(NamedExpression::Socket(Socket::cgroupv2(level))),
+            right: Expression::String(path),
+            op: Operator::EQ,
+        }
+    }
+
+    /// Matches packets received on any of the given interfaces,
+    /// e.g. `iifname { "eth0", "wlan*" }`.
+    ///
+    /// Names ending in `*` match all interfaces with that prefix, see [Expression::String].
+    pub fn iifname(names: impl IntoIterator<Item = impl Into<String>>) -> Match {
+        Match::ifname(MetaKey::Iifname, names)
+    }
+
+    /// Matches packets sent on any of the given interfaces,
+    /// e.g. `oifname { "eth0", "wlan*" }`.
+    ///
+    /// Names ending in `*` match all interfaces with that prefix, see [Expression::String].
+    pub fn oifname(names: impl IntoIterator<Item = impl Into<String>>) -> Match {
+        Match::ifname(MetaKey::Oifname, names)
+    }
+
+    fn ifname(key: MetaKey, names: impl IntoIterator<Item = impl Into<String>>) -> Match {
+        let names = names
+            .into_iter()
+            .map(|name| SetItem::Element(Expression::String(name.into())))
+            .collect();
+        Match {
+            left: Expression::Named(NamedExpression::Meta(Meta { key })),
+            right: Expression::Named(NamedExpression::Set(names)),
+            op: Operator::EQ,
+        }
+    }
+
+    /// Matches connections assigned to the named helper, e.g. `ct helper "ftp"`.
+    ///
+    /// The name is the helper's protocol name known to the kernel, not the name
+    /// of a [ct helper object](crate::schema::CTHelper).
+    pub fn ct_helper(name: impl Into<String>) -> Match {
+        Match {
+            left: Expression::Named(NamedExpression::CT(CT::helper())),
+            right: Expression::String(name.into()),
+            op: Operator::EQ,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 /// Anonymous or named Counter.
+///
+/// A string is read as a reference, an object or `null` as an anonymous counter.
 pub enum Counter {
     /// A counter referenced by name.
     Named(String),
@@ -153,9 +355,69 @@ pub struct Mangle {
     pub value: Expression,
 }
 
+impl Mangle {
+    /// Sets the given `meta` key to `value`, i.e. `meta <key> set <value>`.
+    pub fn meta(key: MetaKey, value: Expression) -> Mangle {
+        Mangle {
+            key: Expression::Named(NamedExpression::Meta(Meta { key })),
+            value,
+        }
+    }
+
+    /// Sets the packet mark, i.e. `meta mark set <value>`.
+    ///
+    /// Setting the mark from a [Numgen](crate::expr::Numgen) or
+    /// [JHash](crate::expr::JHash) expression spreads packets across marks,
+    /// e.g. `meta mark set numgen inc mod 4` for load distribution.
+    pub fn mark(value: impl Into<Expression>) -> Mangle {
+        Mangle::meta(MetaKey::Mark, value.into())
+    }
+
+    /// Sets the packet's security mark from the named `secmark` object,
+    /// i.e. `meta secmark set "<name>"`.
+    pub fn secmark(name: impl Into<String>) -> Mangle {
+        Mangle::meta(MetaKey::Secmark, Expression::String(name.into()))
+    }
+
+    /// Sets bits of the given `meta` key, i.e. `meta <key> set meta <key> | <value>`.
+    pub fn set_mark_or(key: MetaKey, value: u32) -> Mangle {
+        let meta = Expression::Named(NamedExpression::Meta(Meta { key }));
+        Mangle::meta(key, meta.or(value))
+    }
+
+    /// Clears bits of the given `meta` key, i.e. `meta <key> set meta <key> & <value>`.
+    pub fn set_mark_and(key: MetaKey, value: u32) -> Mangle {
+        let meta = Expression::Named(NamedExpression::Meta(Meta { key }));
+        Mangle::meta(key, meta.and(value))
+    }
+
+    /// Toggles bits of the given `meta` key, i.e. `meta <key> set meta <key> ^ <value>`.
+    pub fn set_mark_xor(key: MetaKey, value: u32) -> Mangle {
+        let meta = Expression::Named(NamedExpression::Meta(Meta { key }));
+        Mangle::meta(key, meta.xor(value))
+    }
+
+    /// Sets `key` to the value `map_key` maps to in an anonymous map,
+    /// e.g. `meta mark set ip dscp map { 0x2e : 1, 0x0a : 2 }`.
+    pub fn map(
+        key: Expression,
+        map_key: Expression,
+        mappings: impl IntoIterator<Item = (Expression, Expression)>,
+    ) -> Mangle {
+        Mangle {
+            key,
+            value: Expression::Named(NamedExpression::Map(Box::new(crate::expr::Map::anonymous(
+                map_key, mappings,
+            )))),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 /// Represents an anonymous or named quota object.
+///
+/// A string is read as a reference, an object as an anonymous quota.
 pub enum QuotaOrQuotaRef {
     /// Anonymous quota object.
     Quota(Quota),
@@ -168,6 +430,7 @@ pub enum QuotaOrQuotaRef {
 pub struct Quota {
     /// Quota value.
     pub val: u32,
+    #[serde(default = "default_quota_unit")]
     /// Unit of `val`, e.g. `"kbytes"` or `"mbytes"`. If omitted, defaults to `"bytes"`.
     pub val_unit: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -181,8 +444,14 @@ pub struct Quota {
     pub inv: Option<bool>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+fn default_quota_unit() -> String {
+    "bytes".to_string()
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
 /// Creates an anonymous limit which lives in the rule it appears in.
+///
+/// When serializing a limit in packets, `burst_unit` is omitted.
 pub struct Limit {
     /// Rate value to limit to.
     pub rate: u32,
@@ -203,6 +472,58 @@ pub struct Limit {
     pub inv: Option<bool>,
 }
 
+impl Limit {
+    /// Returns `true` if the limit counts packets rather than bytes.
+    pub fn is_packets(&self) -> bool {
+        self.rate_unit
+            .as_deref()
+            .map_or(true, |unit| unit == "packets")
+    }
+
+    /// Checks that `burst_unit` is only given for limits in bytes.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.is_packets() && self.burst_unit.is_some() {
+            return Err(ValidationError::BurstUnitInPacketLimit);
+        }
+        Ok(())
+    }
+}
+
+/// Serializes the limit, omitting `burst_unit` for limits in packets.
+impl Serialize for Limit {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        fn field<S: SerializeStruct, T: Serialize>(
+            state: &mut S,
+            key: &'static str,
+            value: &Option<T>,
+        ) -> Result<(), S::Error> {
+            match value {
+                Some(value) => state.serialize_field(key, value),
+                None => state.skip_field(key),
+            }
+        }
+
+        let burst_unit = if self.is_packets() {
+            &None
+        } else {
+            &self.burst_unit
+        };
+        let mut state = serializer.serialize_struct("Limit", 6)?;
+        state.serialize_field("rate", &self.rate)?;
+        field(&mut state, "rate_unit", &self.rate_unit)?;
+        field(&mut state, "per", &self.per)?;
+        field(&mut state, "burst", &self.burst)?;
+        field(&mut state, "burst_unit", burst_unit)?;
+        field(&mut state, "inv", &self.inv)?;
+        state.end()
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// Forward a packet to a different destination.
 pub struct Flow {
@@ -212,7 +533,7 @@ pub struct Flow {
     pub flowtable: String,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// Forward a packet to a different destination.
 pub struct FWD {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -244,7 +565,7 @@ pub struct Dup {
     pub dev: Option<Expression>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// Perform Network Address Translation.
 /// Referenced by `SNAT` and `DNAT` statements.
 pub struct NAT {
@@ -262,6 +583,42 @@ pub struct NAT {
     pub flags: Option<HashSet<NATFlag>>,
 }
 
+impl NAT {
+    /// Creates a NAT target translating to the given socket address.
+    ///
+    /// Sets `addr`, `port` and the matching `family`, so the target can
+    /// also be used in `inet` tables.
+    pub fn to_socketaddr(addr: SocketAddr) -> NAT {
+        let family = match addr {
+            SocketAddr::V4(_) => NATFamily::IP,
+            SocketAddr::V6(_) => NATFamily::IP6,
+        };
+        NAT {
+            addr: Some(Expression::String(addr.ip().to_string())),
+            family: Some(family),
+            port: Some(addr.port().into()),
+            flags: None,
+        }
+    }
+
+    /// Checks the NAT options against the family of the containing table.
+    ///
+    /// In `inet` tables, the address family to translate to is ambiguous
+    /// and therefore has to be given explicitly.
+    pub fn validate(&self, family: NfFamily) -> Result<(), ValidationError> {
+        if family == NfFamily::INet && self.family.is_none() {
+            return Err(ValidationError::MissingNatFamily);
+        }
+        Ok(())
+    }
+}
+
+impl From<SocketAddr> for NAT {
+    fn from(addr: SocketAddr) -> Self {
+        NAT::to_socketaddr(addr)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Protocol family for `NAT`.
@@ -280,7 +637,8 @@ pub enum NATFlag {
     Persistent,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RejectRepr")]
 /// Reject the packet and send the given error reply.
 pub struct Reject {
     #[serde(skip_serializing_if = "Option::is_none", rename = "type")]
@@ -288,9 +646,58 @@ pub struct Reject {
     pub _type: Option<RejectType>,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// ICMP code to reject with.
+    ///
+    /// Some nftables releases list numeric codes, which are converted to
+    /// the named code for the reject type if possible.
     pub expr: Option<RejectCode>,
 }
 
+#[derive(Deserialize)]
+/// Serialized form of [Reject], whose code may still be numeric.
+struct RejectRepr {
+    #[serde(rename = "type")]
+    _type: Option<RejectType>,
+    expr: Option<RejectCode>,
+}
+
+impl From<RejectRepr> for Reject {
+    fn from(repr: RejectRepr) -> Self {
+        let expr = match (repr._type, repr.expr) {
+            (Some(_type), Some(RejectCode::Other(code))) => Some(named_reject_code(_type, code)),
+            (_, expr) => expr,
+        };
+        Reject {
+            _type: repr._type,
+            expr,
+        }
+    }
+}
+
+/// Maps a numeric code of the given reject type to its named [RejectCode].
+fn named_reject_code(_type: RejectType, code: u8) -> RejectCode {
+    match (_type, code) {
+        // NFT_REJECT_ICMPX_*
+        (RejectType::ICMPX, 0) => RejectCode::NoRoute,
+        (RejectType::ICMPX, 1) => RejectCode::PortUnreach,
+        (RejectType::ICMPX, 2) => RejectCode::HostUnreach,
+        (RejectType::ICMPX, 3) => RejectCode::AdminProhibited,
+        // ICMP destination unreachable codes
+        (RejectType::ICMP, 0) => RejectCode::NetUnreach,
+        (RejectType::ICMP, 1) => RejectCode::HostUnreach,
+        (RejectType::ICMP, 2) => RejectCode::ProtUnreach,
+        (RejectType::ICMP, 3) => RejectCode::PortUnreach,
+        (RejectType::ICMP, 9) => RejectCode::NetProhibited,
+        (RejectType::ICMP, 10) => RejectCode::HostProhibited,
+        (RejectType::ICMP, 13) => RejectCode::AdminProhibited,
+        // ICMPv6 destination unreachable codes
+        (RejectType::ICMPv6, 0) => RejectCode::NoRoute,
+        (RejectType::ICMPv6, 1) => RejectCode::AdminProhibited,
+        (RejectType::ICMPv6, 3) => RejectCode::AddrUnreach,
+        (RejectType::ICMPv6, 4) => RejectCode::PortUnreach,
+        _ => RejectCode::Other(code),
+    }
+}
+
 impl Reject {
     pub fn new(_type: Option<RejectType>, code: Option<RejectCode>) -> Reject {
         Reject { _type, expr: code }
@@ -319,15 +726,32 @@ pub struct Set {
     pub set: String,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+/// Dynamically add/update elements of a named map from the packet path,
+/// e.g. `map add @m { ip saddr : 1 }`.
+///
+/// Unlike the [map expression](crate::expr::Map), which looks up a value,
+/// this statement changes the map.
+pub struct MapUpdate {
+    /// Operator on map.
+    pub op: SetOp,
+    /// Key of the element to add or update.
+    pub elem: Expression,
+    /// Value of the element.
+    pub data: Expression,
+    /// Map reference, e.g. `"@m"`.
+    pub map: String,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
-/// Operators on `Set`.
+/// Operators on `Set` and `MapUpdate`.
 pub enum SetOp {
     Add,
     Update,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// Log the packet.
 /// All properties are optional.
 pub struct Log {
@@ -371,6 +795,47 @@ impl Log {
             flags: None,
         }
     }
+
+    /// Checks the log statement for mutually exclusive options.
+    ///
+    /// Logging either goes to syslog (configured by `level` and `flags`) or to
+    /// nflog (configured by `group`, `snaplen` and `queue_threshold`), so
+    /// options of both backends cannot be combined.
+    /// Level `audit` does not support any further options.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let nflog_option = if self.group.is_some() {
+            Some("group")
+        } else if self.snaplen.is_some() {
+            Some("snaplen")
+        } else if self.queue_threshold.is_some() {
+            Some("queue-threshold")
+        } else {
+            None
+        };
+        if let Some(nflog_option) = nflog_option {
+            if self.level.is_some() {
+                return Err(ValidationError::ConflictingLogOptions(
+                    "level",
+                    nflog_option,
+                ));
+            }
+            if self.flags.is_some() {
+                return Err(ValidationError::ConflictingLogOptions(
+                    "flags",
+                    nflog_option,
+                ));
+            }
+        }
+        if self.level == Some(LogLevel::Audit) {
+            if let Some(other) = nflog_option
+                .or(self.prefix.as_ref().map(|_| "prefix"))
+                .or(self.flags.as_ref().map(|_| "flags"))
+            {
+                return Err(ValidationError::ConflictingLogOptions("level audit", other));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -421,6 +886,10 @@ pub struct Meter {
 /// Queue the packet to userspace.
 pub struct Queue {
     /// Queue number.
+    ///
+    /// Usually a constant (see [Queue::to]) or a [range](crate::expr::Range)
+    /// of queues. An expression such as a [numgen](crate::expr::Numgen) or
+    /// [jhash](crate::expr::JHash) selects the queue per packet (`queue to numgen ...`).
     pub num: Expression,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -428,6 +897,33 @@ pub struct Queue {
     pub flags: Option<HashSet<QueueFlag>>,
 }
 
+impl Queue {
+    /// Creates a queue statement body queueing packets to the constant queue `num`,
+    /// to be wrapped in [Statement::Queue].
+    pub fn to(num: u16) -> Queue {
+        Queue {
+            num: Expression::Number(num.into()),
+            flags: None,
+        }
+    }
+
+    /// Checks that constant queue numbers, including range bounds, fit in 16 bits.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let numbers = match &self.num {
+            Expression::Range(range) => range.range.iter().collect(),
+            num => vec![num],
+        };
+        for num in numbers {
+            if let Expression::Number(n) = num {
+                if *n > u16::MAX.into() {
+                    return Err(ValidationError::QueueNumberOutOfRange(*n));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Hash)]
 #[serde(rename_all = "lowercase")]
 /// Flags of `Queue`.
@@ -450,6 +946,11 @@ pub struct VerdictMap {
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "ct count")]
 /// Limit the number of connections using conntrack.
+///
+/// Connection limits are anonymous only: unlike [counters](crate::schema::Counter)
+/// or [quotas](crate::schema::Quota), nftables has no named `ct count` object.
+/// To limit connections per key, add the statement to the elements of a
+/// dynamic set instead, e.g. `add @perhost { ip saddr ct count over 10 }`.
 pub struct CTCount {
     /// Connection count threshold.
     pub val: Expression,
@@ -459,10 +960,10 @@ pub struct CTCount {
     pub inv: Option<bool>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-/// Limit the number of connections using conntrack.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+/// Intercept new TCP connections and perform the 3-way handshake on behalf of the backend.
 ///
-/// Anonymous synproxy was requires **nftables 0.9.2 or newer**.
+/// Anonymous synproxy requires **nftables 0.9.2 or newer**.
 pub struct SynProxy {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// maximum segment size (must match your backend server)
@@ -470,24 +971,68 @@ pub struct SynProxy {
     #[serde(skip_serializing_if = "Option::is_none")]
     /// window scale (must match your backend server)
     pub wscale: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "single_string_to_option_hashset"
+    )]
     /// The synproxy's [flags][crate::types::SynProxyFlag].
     pub flags: Option<HashSet<SynProxyFlag>>,
 }
 
+impl SynProxy {
+    /// Creates a synproxy statement matching the TCP options of the backend server.
+    ///
+    /// `mss` and `wscale` have to equal the values the backend announces, as
+    /// they differ e.g. between IPv4 and IPv6 paths. `timestamp` and `sack`
+    /// pass the client's timestamp and selective acknowledgement options on to
+    /// the backend and should be enabled if the backend supports them.
+    pub fn for_backend(mss: u32, wscale: u32, timestamp: bool, sack: bool) -> SynProxy {
+        let mut flags = HashSet::new();
+        if timestamp {
+            flags.insert(SynProxyFlag::Timestamp);
+        }
+        if sack {
+            flags.insert(SynProxyFlag::SackPerm);
+        }
+        SynProxy {
+            mss: Some(mss),
+            wscale: Some(wscale),
+            flags: if flags.is_empty() { None } else { Some(flags) },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Redirects the packet to a local socket without changing the packet header in any way.
 pub struct TProxy {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub family: Option<String>,
-    pub port: u16,
+    /// Port of the local socket, either a [number](Expression::Number) or an
+    /// expression such as a map lookup (`tproxy to :tcp dport map { ... }`).
+    pub port: Expression,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub addr: Option<String>,
 }
 
+impl TProxy {
+    /// Creates a tproxy statement redirecting to the given local port, i.e. `tproxy to :<port>`.
+    pub fn to_port(port: u16) -> TProxy {
+        TProxy {
+            family: None,
+            port: Expression::Number(port.into()),
+            addr: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
 /// Represents an operator for `Match`.
+///
+/// Matches on this enum need a wildcard arm, as operators of newer
+/// nftables releases may be added in minor releases.
 pub enum Operator {
     #[serde(rename = "&")]
     /// Binary AND (`&`)
@@ -517,12 +1062,12 @@ pub enum Operator {
     /// Not equal (`!=`)
     NEQ,
 
-    #[serde(rename = ">")]
-    /// Less than (`>`)
+    #[serde(rename = "<")]
+    /// Less than (`<`)
     LT,
 
-    #[serde(rename = "<")]
-    /// Greater than (`<`)
+    #[serde(rename = ">")]
+    /// Greater than (`>`)
     GT,
 
     #[serde(rename = "<=")]
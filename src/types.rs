@@ -1,10 +1,12 @@
 use serde::{Deserialize, Serialize};
+use strum_macros::AsRefStr;
 
 /// Families in nftables.
 ///
 /// See <https://wiki.nftables.org/wiki-nftables/index.php/Nftables_families>.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, AsRefStr)]
 #[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 pub enum NfFamily {
     IP,
     IP6,
@@ -14,6 +16,20 @@ pub enum NfFamily {
     NetDev,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+/// Kinds of named ruleset elements, as checked by [object_exists](crate::helper::object_exists).
+pub enum NfObjectKind {
+    Table,
+    Chain,
+    Set,
+    Map,
+    FlowTable,
+    Counter,
+    Quota,
+    Limit,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Represents the type of a Chain.
@@ -46,6 +62,49 @@ pub enum NfHook {
     Egress,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+/// A named priority, standing for a fixed numeric priority of a hook.
+///
+/// See <https://wiki.nftables.org/wiki-nftables/index.php/Netfilter_hooks#Priority_within_hook>.
+pub enum NfPriority {
+    /// `raw` (-300).
+    Raw,
+    /// `mangle` (-150).
+    Mangle,
+    /// `dstnat` (-100).
+    DstNat,
+    /// `filter` (0).
+    Filter,
+    /// `security` (50).
+    Security,
+    /// `srcnat` (100).
+    SrcNat,
+}
+
+impl NfPriority {
+    /// Returns the numeric priority this name stands for.
+    pub fn value(self) -> i32 {
+        match self {
+            NfPriority::Raw => -300,
+            NfPriority::Mangle => -150,
+            NfPriority::DstNat => -100,
+            NfPriority::Filter => 0,
+            NfPriority::Security => 50,
+            NfPriority::SrcNat => 100,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Describes a flow table's flags.
+pub enum NfFlowTableFlag {
+    /// Offload flows to hardware.
+    Offload,
+    /// Count packets and bytes of offloaded flows.
+    Counter,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// Represents a conntrack helper protocol.
@@ -89,6 +148,12 @@ pub enum RejectCode {
     #[serde(rename = "addr-unreachable")]
     /// Address unreachable (ICMPv6)
     AddrUnreach,
+    #[serde(untagged)]
+    /// A numeric code without symbolic name for the [reject type](crate::stmt::RejectType).
+    ///
+    /// Numeric codes of known names are converted to the named variant when
+    /// a [Reject](crate::stmt::Reject) statement is deserialized.
+    Other(u8),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
@@ -102,9 +167,11 @@ pub enum SynProxyFlag {
     SackPerm,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 /// A time unit (used by [limits][crate::schema::Limit]).
+///
+/// Units are ordered by their duration.
 pub enum NfTimeUnit {
     /// A second.
     Second,
@@ -117,3 +184,16 @@ pub enum NfTimeUnit {
     /// A week (604800 seconds).
     Week,
 }
+
+impl NfTimeUnit {
+    /// Returns the duration of the unit in seconds.
+    pub fn as_seconds(self) -> u64 {
+        match self {
+            NfTimeUnit::Second => 1,
+            NfTimeUnit::Minute => 60,
+            NfTimeUnit::Hour => 3600,
+            NfTimeUnit::Day => 86400,
+            NfTimeUnit::Week => 604800,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use thiserror::Error;
+
+use crate::types::NfFamily;
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+/// Describes why an object was rejected by a `validate()` check.
+///
+/// Validation is best-effort: it catches mistakes that nftables would
+/// otherwise only report when the ruleset is applied.
+pub enum ValidationError {
+    #[error("concatenated set type needs at least two components, got {0}")]
+    /// A [concatenated set type](crate::schema::SetTypeValue::Concatenated) with less than two components.
+    InvalidConcatenation(usize),
+    #[error("base chain {0} has no policy")]
+    /// A base chain (i.e. a chain with a hook) without a [policy](crate::types::NfChainPolicy).
+    MissingChainPolicy(String),
+    #[error("set {0} needs the interval flag to hold ranges or prefixes")]
+    /// An [element](crate::schema::Element) with ranges or prefixes targeting a set without the interval flag.
+    IntervalInPlainSet(String),
+    #[error("{0} needs the interval flag to use auto-merge")]
+    /// A [set](crate::schema::Set) or [map](crate::schema::Map) with `auto-merge` but without the interval flag.
+    AutoMergeWithoutInterval(String),
+    #[error("set {set} has {expected} type components, but element has {found}")]
+    /// An [element](crate::schema::Element) whose number of concatenated components differs from its set's type.
+    ArityMismatch {
+        set: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("unknown payload protocol {0}")]
+    /// A [payload](crate::expr::PayloadField) referencing an unknown protocol header.
+    UnknownPayloadProtocol(String),
+    #[error("unknown field {field} in payload protocol {protocol}")]
+    /// A [payload](crate::expr::PayloadField) referencing an unknown field of a known protocol header.
+    UnknownPayloadField { protocol: String, field: String },
+    #[error("unknown tcp option {0}")]
+    /// A [tcp option](crate::expr::TcpOption) unknown to nftables.
+    UnknownTcpOption(String),
+    #[error("unknown field {field} in tcp option {option}")]
+    /// A [tcp option](crate::expr::TcpOption) referencing an unknown field of a known option.
+    UnknownTcpOptionField { option: String, field: String },
+    #[error("log options {0} and {1} are mutually exclusive")]
+    /// A [log statement](crate::stmt::Log) combining options of different log backends.
+    ConflictingLogOptions(&'static str, &'static str),
+    #[error("NAT statement in inet table has no family")]
+    /// A [NAT statement](crate::stmt::NAT) in an `inet` table without a [family](crate::stmt::NATFamily).
+    MissingNatFamily,
+    #[error("burst unit given for limit in packets")]
+    /// A [limit statement](crate::stmt::Limit) in packets with a `burst_unit`, which only applies to limits in bytes.
+    BurstUnitInPacketLimit,
+    #[error(
+        "comment is {0} bytes long, at most {max} bytes are allowed",
+        max = crate::schema::MAX_COMMENT_LEN
+    )]
+    /// A comment of a [rule](crate::schema::Rule), [set](crate::schema::Set) or
+    /// [map](crate::schema::Map) exceeding [MAX_COMMENT_LEN](crate::schema::MAX_COMMENT_LEN).
+    CommentTooLong(usize),
+    #[error("comment contains invalid character {0:?}")]
+    /// A comment containing a quote or line break.
+    InvalidCommentCharacter(char),
+    #[error("synproxy {0} sets only one of mss and wscale")]
+    /// A [synproxy](crate::schema::SynProxy) with either `mss` or `wscale`, but not both.
+    IncompleteSynProxy(String),
+    #[error("queue number {0} exceeds 65535")]
+    /// A [queue statement](crate::stmt::Queue) with a constant queue number not fitting in 16 bits.
+    QueueNumberOutOfRange(u64),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+/// Describes a reference within a [Batch](crate::batch::Batch) to an object
+/// that is not defined in the same batch.
+///
+/// Reported by [Batch::validate_references](crate::batch::Batch::validate_references).
+/// The referenced object may still exist in the live ruleset.
+pub enum ReferenceWarning {
+    /// A `jump` or `goto` to a chain not defined in the batch.
+    UndefinedChain {
+        family: NfFamily,
+        table: String,
+        chain: String,
+    },
+    /// A [ct helper](crate::schema::CTHelper) for `helper_protocol`, assigned
+    /// in a rule that matches `matched_protocol` before.
+    CTHelperProtocolMismatch {
+        family: NfFamily,
+        table: String,
+        helper: String,
+        helper_protocol: String,
+        matched_protocol: String,
+    },
+    /// A `reject with tcp reset` in a rule that does not match TCP traffic before.
+    ///
+    /// `matched_protocol` is the other layer 4 protocol the rule matches, if any.
+    TcpResetWithoutTcp {
+        family: NfFamily,
+        table: String,
+        chain: String,
+        matched_protocol: Option<String>,
+    },
+}
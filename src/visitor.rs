@@ -1,5 +1,5 @@
-use serde::{de, Deserialize};
-use std::{collections::HashSet, fmt::Formatter, marker::PhantomData, str::FromStr};
+use serde::{de, Deserialize, Serializer};
+use std::{collections::HashSet, fmt::Formatter, hash::Hash, marker::PhantomData};
 
 use crate::stmt::LogFlag;
 
@@ -81,17 +81,85 @@ where
         where
             E: de::Error,
         {
-            let mut h: HashSet<LogFlag> = HashSet::new();
-            h.insert(LogFlag::from_str(value).unwrap());
-            Ok(Some(h))
+            let flag = LogFlag::deserialize(de::value::StrDeserializer::<E>::new(value))?;
+            Ok(Some(HashSet::from([flag])))
         }
 
         fn visit_seq<S>(self, visitor: S) -> Result<Self::Value, S::Error>
         where
             S: de::SeqAccess<'de>,
         {
-            Deserialize::deserialize(de::value::SeqAccessDeserializer::new(visitor))
+            let flags: HashSet<LogFlag> =
+                Deserialize::deserialize(de::value::SeqAccessDeserializer::new(visitor))?;
+            Ok(Some(flags))
         }
     }
     deserializer.deserialize_any(LogFlagSet(PhantomData))
 }
+
+/// Deserialize a string or string sequence into a `HashSet` of string-like values, e.g. flags.
+pub fn single_string_to_hashset<'de, D, T>(deserializer: D) -> Result<HashSet<T>, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: Deserialize<'de> + Eq + Hash,
+{
+    struct StringOrSet<T>(PhantomData<HashSet<T>>);
+    impl<'de, T> de::Visitor<'de> for StringOrSet<T>
+    where
+        T: Deserialize<'de> + Eq + Hash,
+    {
+        type Value = HashSet<T>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("single string or list of strings")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            let item = T::deserialize(de::value::StrDeserializer::<E>::new(value))?;
+            Ok(HashSet::from([item]))
+        }
+
+        fn visit_seq<S>(self, visitor: S) -> Result<Self::Value, S::Error>
+        where
+            S: de::SeqAccess<'de>,
+        {
+            Deserialize::deserialize(de::value::SeqAccessDeserializer::new(visitor))
+        }
+    }
+    deserializer.deserialize_any(StringOrSet(PhantomData))
+}
+
+/// Deserialize null, a string or string sequence into an `Option<HashSet>` of string-like values.
+pub fn single_string_to_option_hashset<'de, D, T>(
+    deserializer: D,
+) -> Result<Option<HashSet<T>>, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: Deserialize<'de> + Eq + Hash,
+{
+    let value: Option<HashSetOrString<T>> = Deserialize::deserialize(deserializer)?;
+    Ok(value.map(|v| v.0))
+}
+
+/// Helper to deserialize an optional single string or string sequence via [single_string_to_hashset].
+struct HashSetOrString<T>(HashSet<T>);
+
+impl<'de, T> Deserialize<'de> for HashSetOrString<T>
+where
+    T: Deserialize<'de> + Eq + Hash,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        single_string_to_hashset(deserializer).map(HashSetOrString)
+    }
+}
+
+/// Serializes a unit variant as `{"<variant>": null}` instead of a plain string, as nftables expects.
+pub fn serialize_none<S: Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_none()
+}
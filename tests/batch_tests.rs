@@ -0,0 +1,410 @@
+use nftables::{
+    batch::Batch,
+    expr::{Expression, NamedExpression, Payload, PayloadField, Verdict},
+    schema::{
+        CTHelper, Chain, Element, FlushObject, NfCmd, NfListObject, NfObject, Rule, Set, SetFlag,
+        Table,
+    },
+    stmt::{Counter, JumpTarget, Match, Operator, Reject, RejectType, Statement},
+    types::{NfChainPolicy, NfChainType, NfFamily, NfHook},
+    validation::ReferenceWarning,
+};
+use serde_json::json;
+
+#[test]
+/// Objects added in a table scope equal the explicitly constructed ones.
+fn test_add_table_with() {
+    let mut scoped = Batch::new();
+    scoped.add_table_with(NfFamily::IP6, "scoped", |t| {
+        t.chain(Chain {
+            name: "input".to_string(),
+            _type: Some(NfChainType::Filter),
+            hook: Some(NfHook::Input),
+            ..Chain::default()
+        });
+        t.set(Set {
+            name: "blocked".to_string(),
+            ..Set::default()
+        });
+        t.rule(Rule {
+            chain: "input".to_string(),
+            expr: vec![Statement::Accept(None)],
+            ..Rule::default()
+        });
+    });
+
+    let mut explicit = Batch::new();
+    explicit.add(NfListObject::Table(Table {
+        family: NfFamily::IP6,
+        name: "scoped".to_string(),
+        handle: None,
+    }));
+    explicit.add(NfListObject::Chain(Chain {
+        family: NfFamily::IP6,
+        table: "scoped".to_string(),
+        name: "input".to_string(),
+        _type: Some(NfChainType::Filter),
+        hook: Some(NfHook::Input),
+        ..Chain::default()
+    }));
+    explicit.add(NfListObject::Set(Set {
+        family: NfFamily::IP6,
+        table: "scoped".to_string(),
+        name: "blocked".to_string(),
+        ..Set::default()
+    }));
+    explicit.add(NfListObject::Rule(Rule {
+        family: NfFamily::IP6,
+        table: "scoped".to_string(),
+        chain: "input".to_string(),
+        expr: vec![Statement::Accept(None)],
+        ..Rule::default()
+    }));
+
+    assert_eq!(explicit.to_nftables(), scoped.to_nftables());
+}
+
+#[test]
+/// Large element lists are split into multiple commands.
+fn test_add_elements_chunked() {
+    let element = Element {
+        elem: (0..10_000).map(Expression::Number).collect(),
+        ..Element::default()
+    };
+    let mut batch = Batch::new();
+    batch.add_elements_chunked(element, 4096);
+    let objects = batch.to_nftables().objects;
+    assert_eq!(3, objects.len());
+
+    let lens: Vec<usize> = objects
+        .iter()
+        .map(|obj| match obj {
+            NfObject::CmdObject(NfCmd::Add(NfListObject::Element(e))) => e.elem.len(),
+            _ => panic!("expected add element command, got {:?}", obj),
+        })
+        .collect();
+    assert_eq!(vec![4096, 4096, 1808], lens);
+}
+
+#[test]
+/// Duplicate commands are removed, distinct ones are kept in order.
+fn test_dedup() {
+    let table = NfListObject::Table(Table::default());
+    let chain = NfListObject::Chain(Chain::default());
+    let set = |flags: [SetFlag; 3]| {
+        NfListObject::Set(Set {
+            flags: Some(flags.into_iter().collect()),
+            ..Set::default()
+        })
+    };
+    let mut batch = Batch::new();
+    batch.add(table.clone());
+    batch.add(chain.clone());
+    batch.add(set([SetFlag::Interval, SetFlag::Timeout, SetFlag::Dynamic]));
+    batch.add(table.clone());
+    batch.add(set([SetFlag::Dynamic, SetFlag::Timeout, SetFlag::Interval]));
+    batch.delete(table.clone());
+    batch.dedup();
+
+    let mut expected = Batch::new();
+    expected.add(table.clone());
+    expected.add(chain);
+    expected.add(set([SetFlag::Interval, SetFlag::Timeout, SetFlag::Dynamic]));
+    expected.delete(table);
+    assert_eq!(expected.to_nftables(), batch.to_nftables());
+}
+
+#[test]
+/// Jumps to chains missing from the batch are reported, others are not.
+fn test_validate_references_jump() {
+    let mut batch = Batch::new();
+    batch.add(NfListObject::Chain(Chain {
+        name: "input".to_string(),
+        ..Chain::default()
+    }));
+    batch.add(NfListObject::Chain(Chain {
+        name: "allowed".to_string(),
+        ..Chain::default()
+    }));
+    batch.add(NfListObject::Rule(Rule {
+        chain: "input".to_string(),
+        expr: vec![Statement::Jump(JumpTarget {
+            target: "allowed".to_string(),
+        })],
+        ..Rule::default()
+    }));
+    assert_eq!(Vec::<ReferenceWarning>::new(), batch.validate_references());
+
+    batch.add(NfListObject::Rule(Rule {
+        chain: "input".to_string(),
+        expr: vec![Statement::Goto(JumpTarget {
+            target: "missing".to_string(),
+        })],
+        ..Rule::default()
+    }));
+    assert_eq!(
+        vec![ReferenceWarning::UndefinedChain {
+            family: NfFamily::INet,
+            table: "filter".to_string(),
+            chain: "missing".to_string(),
+        }],
+        batch.validate_references()
+    );
+}
+
+/// Returns a batch with an ftp helper and a rule assigning it after matching `protocol dport 21`.
+fn ct_helper_batch(protocol: &str) -> Batch {
+    let mut batch = Batch::new();
+    batch.add(NfListObject::CTHelper(CTHelper {
+        name: "ftp-standard".to_string(),
+        _type: "ftp".to_string(),
+        protocol: Some("tcp".to_string()),
+        ..CTHelper::default()
+    }));
+    batch.add(NfListObject::Rule(Rule {
+        expr: vec![
+            Statement::Match(Match {
+                left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+                    PayloadField {
+                        protocol: protocol.into(),
+                        field: "dport".to_string(),
+                    },
+                ))),
+                right: Expression::Number(21),
+                op: Operator::EQ,
+            }),
+            Statement::CTHelper("ftp-standard".to_string()),
+        ],
+        ..Rule::default()
+    }));
+    batch
+}
+
+#[test]
+/// Assigning a tcp ct helper in a tcp rule is fine.
+fn test_validate_references_ct_helper_tcp() {
+    assert_eq!(
+        Vec::<ReferenceWarning>::new(),
+        ct_helper_batch("tcp").validate_references()
+    );
+}
+
+#[test]
+/// Assigning a tcp ct helper in a udp rule is reported.
+fn test_validate_references_ct_helper_udp() {
+    assert_eq!(
+        vec![ReferenceWarning::CTHelperProtocolMismatch {
+            family: NfFamily::INet,
+            table: "filter".to_string(),
+            helper: "ftp-standard".to_string(),
+            helper_protocol: "tcp".to_string(),
+            matched_protocol: "udp".to_string(),
+        }],
+        ct_helper_batch("udp").validate_references()
+    );
+}
+
+#[test]
+/// Rejecting with a tcp reset is reported unless the rule matches tcp.
+fn test_validate_references_tcp_reset() {
+    let cases = [
+        ("tcp", vec![]),
+        (
+            "udp",
+            vec![ReferenceWarning::TcpResetWithoutTcp {
+                family: NfFamily::INet,
+                table: "filter".to_string(),
+                chain: "forward".to_string(),
+                matched_protocol: Some("udp".to_string()),
+            }],
+        ),
+    ];
+    for (protocol, expected) in cases {
+        let mut batch = Batch::new();
+        batch.add(NfListObject::Rule(Rule {
+            expr: vec![
+                Statement::Match(Match {
+                    left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+                        PayloadField::new(protocol, "dport"),
+                    ))),
+                    right: Expression::Number(22),
+                    op: Operator::EQ,
+                }),
+                Statement::Reject(Some(Reject::new(Some(RejectType::TCPReset), None))),
+            ],
+            ..Rule::default()
+        }));
+        assert_eq!(expected, batch.validate_references(), "{}", protocol);
+    }
+}
+
+#[test]
+/// Verdict map elements added through a batch round-trip as mappings.
+fn test_add_verdict_map_elements() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add element inet t m { 80 : jump http, 443 : jump https }'
+    // ```
+    let jump = |target: &str| {
+        Expression::Verdict(Verdict::Jump(JumpTarget {
+            target: target.to_string(),
+        }))
+    };
+    let mut batch = Batch::new();
+    batch.add(NfListObject::Element(Element::mapping(
+        NfFamily::INet,
+        "t",
+        "m",
+        [(80, jump("http")), (443, jump("https"))],
+    )));
+    let nftables = batch.to_nftables();
+    let json = json!({"nftables": [{"add": {"element": {"family": "inet", "table": "t", "name": "m",
+        "elem": [[80, {"jump": {"target": "http"}}], [443, {"jump": {"target": "https"}}]]}}}]});
+    assert_eq!(json, serde_json::to_value(&nftables).unwrap());
+    let parsed: nftables::schema::Nftables = serde_json::from_value(json).unwrap();
+    assert_eq!(nftables, parsed);
+}
+
+#[test]
+/// `flush ruleset` is added as a flush command without object.
+fn test_flush_ruleset() {
+    let mut batch = Batch::new();
+    batch.flush_ruleset();
+    let nftables = batch.to_nftables();
+    assert_eq!(
+        vec![NfObject::CmdObject(NfCmd::Flush(FlushObject::Ruleset(
+            None
+        )))],
+        nftables.objects
+    );
+    assert_eq!(
+        json!({"nftables": [{"flush": {"ruleset": null}}]}),
+        serde_json::to_value(&nftables).unwrap()
+    );
+}
+
+#[test]
+/// Commands are rendered in nft syntax without running nft.
+fn test_explain() {
+    let mut batch = Batch::new();
+    batch.flush_ruleset();
+    batch.add(NfListObject::Table(Table {
+        family: NfFamily::INet,
+        name: "filter".to_string(),
+        ..Table::default()
+    }));
+    batch.add(NfListObject::Chain(Chain {
+        family: NfFamily::INet,
+        table: "filter".to_string(),
+        name: "input".to_string(),
+        _type: Some(NfChainType::Filter),
+        hook: Some(NfHook::Input),
+        prio: Some(0),
+        policy: Some(NfChainPolicy::Drop),
+        ..Chain::default()
+    }));
+    batch.add(NfListObject::Rule(Rule {
+        family: NfFamily::INet,
+        table: "filter".to_string(),
+        chain: "input".to_string(),
+        expr: vec![
+            Statement::Match(Match {
+                left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+                    PayloadField::new("tcp", "dport"),
+                ))),
+                right: Expression::Number(22),
+                op: Operator::EQ,
+            }),
+            Statement::Counter(Counter::Anonymous(None)),
+            Statement::Accept(None),
+        ],
+        comment: Some("ssh".to_string()),
+        ..Rule::default()
+    }));
+    batch.delete(NfListObject::Rule(Rule {
+        family: NfFamily::INet,
+        table: "filter".to_string(),
+        chain: "input".to_string(),
+        handle: Some(5),
+        ..Rule::default()
+    }));
+    batch.add(NfListObject::Element(Element {
+        family: NfFamily::INet,
+        table: "filter".to_string(),
+        name: "blocked".to_string(),
+        elem: vec![
+            Expression::String("10.0.0.1".to_string()),
+            Expression::String("10.0.0.2".to_string()),
+        ],
+    }));
+
+    assert_eq!(
+        vec![
+            "flush ruleset",
+            "add table inet filter",
+            "add chain inet filter input { type filter hook input priority 0; policy drop; }",
+            "add rule inet filter input tcp dport 22 counter accept comment \"ssh\"",
+            "delete rule inet filter input handle 5",
+            "add element inet filter blocked { 10.0.0.1, 10.0.0.2 }",
+        ],
+        batch.explain()
+    );
+}
+
+#[test]
+/// String literals are quoted and escaped, references and addresses are not.
+fn test_explain_quotes_strings() {
+    let mut batch = Batch::new();
+    batch.add(NfListObject::Rule(Rule::new(
+        NfFamily::INet,
+        "filter",
+        "input",
+        [
+            Statement::Match(Match::iifname(["eth0", "wg\\\"0"])),
+            Statement::Match(Match {
+                left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+                    PayloadField::new("ip", "saddr"),
+                ))),
+                right: Expression::String("@blocked".to_string()),
+                op: Operator::EQ,
+            }),
+            Statement::Match(Match {
+                left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+                    PayloadField::new("ip", "daddr"),
+                ))),
+                right: Expression::String("192.0.2.0/24".to_string()),
+                op: Operator::NEQ,
+            }),
+            Statement::Counter(Counter::Named("ssh".to_string())),
+            Statement::Accept(None),
+        ],
+    )));
+
+    assert_eq!(
+        vec![
+            "add rule inet filter input meta iifname { \"eth0\", \"wg\\\\\\\"0\" } \
+             ip saddr @blocked ip daddr != 192.0.2.0/24 counter name \"ssh\" accept"
+        ],
+        batch.explain()
+    );
+}
+
+#[test]
+/// Rules can be inserted by index or before a handle.
+fn test_insert_positioning() {
+    let rule = Rule::new(NfFamily::INet, "filter", "input", [Statement::Accept(None)]);
+    let mut batch = Batch::new();
+    // nft 'insert rule inet filter input index 2 accept'
+    batch.insert_at_index(rule.clone(), 2);
+    // nft 'insert rule inet filter input handle 7 accept'
+    batch.insert_before_handle(rule, 7);
+    assert_eq!(
+        json!({"nftables": [
+            {"insert": {"rule": {"family": "inet", "table": "filter", "chain": "input",
+                "expr": [{"accept": null}], "index": 2}}},
+            {"insert": {"rule": {"family": "inet", "table": "filter", "chain": "input",
+                "expr": [{"accept": null}], "handle": 7}}}
+        ]}),
+        serde_json::to_value(batch.to_nftables()).unwrap()
+    );
+}
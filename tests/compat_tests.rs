@@ -0,0 +1,52 @@
+use nftables::{
+    compat::{self, CompatMode},
+    expr::{Expression, Meta, MetaKey, NamedExpression},
+    schema::{NfListObject, NfObject},
+    stmt::{Mangle, Statement},
+};
+
+const LEGACY_MANGLE: &str = include_str!("../resources/test/compat/mangle-left-right.json");
+
+#[test]
+/// Mangle statements with `left`/`right` from older nftables releases are parsed in compat mode.
+fn test_compat_mangle_left_right() {
+    let nftables = compat::from_str(LEGACY_MANGLE, CompatMode::Auto).unwrap();
+    let rule = nftables
+        .objects
+        .iter()
+        .find_map(|obj| match obj {
+            NfObject::ListObject(obj) => match obj.as_ref() {
+                NfListObject::Rule(rule) => Some(rule),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(
+        vec![Statement::Mangle(Mangle {
+            key: Expression::Named(NamedExpression::Meta(Meta { key: MetaKey::Mark })),
+            value: Expression::Number(1),
+        })],
+        rule.expr
+    );
+    assert_eq!(
+        nftables,
+        compat::from_str(LEGACY_MANGLE, CompatMode::Legacy).unwrap()
+    );
+}
+
+#[test]
+/// Without compat mode, the legacy property names are rejected.
+fn test_compat_off() {
+    assert!(compat::from_str(LEGACY_MANGLE, CompatMode::Off).is_err());
+}
+
+#[test]
+/// Current documents are not touched in auto mode.
+fn test_compat_auto_current_schema() {
+    let current = include_str!("../resources/test/json/basic.json");
+    assert_eq!(
+        serde_json::from_str::<nftables::schema::Nftables>(current).unwrap(),
+        compat::from_str(current, CompatMode::Auto).unwrap()
+    );
+}
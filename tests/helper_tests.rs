@@ -1,11 +1,11 @@
-use std::vec;
+use std::{cell::RefCell, os::unix::fs::PermissionsExt, path::PathBuf, vec};
 
 use nftables::{
     batch::Batch,
     expr,
-    helper::{self, NftablesError},
+    helper::{self, NftablesError, RulesetTransport},
     schema::{self, Table},
-    types,
+    stmt, types,
 };
 use serial_test::serial;
 
@@ -18,6 +18,18 @@ fn test_list_ruleset() {
     helper::get_current_ruleset(None, None).unwrap();
 }
 
+#[test]
+#[ignore]
+#[serial]
+/// The raw output returned with the parsed ruleset reparses to the same ruleset.
+fn test_list_ruleset_with_raw() {
+    flush_ruleset().expect("failed to flush ruleset");
+    helper::apply_ruleset(&example_ruleset(false), None, None).unwrap();
+    let (ruleset, raw) = helper::get_current_ruleset_with_raw(None, None).unwrap();
+    assert_eq!(ruleset, raw.parse::<schema::Nftables>().unwrap());
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
 #[test]
 #[ignore]
 /// Attempts to read current ruleset from nftables using non-existing nft binary.
@@ -102,6 +114,7 @@ fn example_ruleset(with_undo: bool) -> schema::Nftables {
         elem: None,
         timeout: None,
         gc_interval: None,
+        auto_merge: None,
         size: None,
         comment: None,
     }));
@@ -118,6 +131,7 @@ fn example_ruleset(with_undo: bool) -> schema::Nftables {
         elem: None,
         timeout: None,
         gc_interval: None,
+        auto_merge: None,
         size: None,
         comment: None,
     }));
@@ -143,7 +157,7 @@ fn example_ruleset(with_undo: bool) -> schema::Nftables {
 
 fn get_flush_ruleset() -> schema::Nftables {
     let mut batch = Batch::new();
-    batch.add_cmd(schema::NfCmd::Flush(schema::FlushObject::Ruleset(None)));
+    batch.flush_ruleset();
     batch.to_nftables()
 }
 
@@ -151,3 +165,607 @@ fn flush_ruleset() -> Result<(), NftablesError> {
     let ruleset = get_flush_ruleset();
     nftables::helper::apply_ruleset(&ruleset, None, None)
 }
+
+#[test]
+#[ignore]
+#[serial]
+/// Observes the `add table` event caused by applying a ruleset.
+fn test_monitor() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let mut monitor = helper::monitor(None).unwrap();
+    // give nft some time to subscribe to ruleset events
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let mut batch = Batch::new();
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::IP,
+        name: "test-table-monitor".to_string(),
+        ..Table::default()
+    }));
+    helper::apply_ruleset(&batch.to_nftables(), None, None).unwrap();
+
+    let event = monitor.next().unwrap().unwrap();
+    match event {
+        schema::MonitorEvent::Add(schema::NfListObject::Table(table)) => {
+            assert_eq!("test-table-monitor", table.name);
+        }
+        other => panic!("unexpected monitor event: {:?}", other),
+    }
+}
+
+#[test]
+/// An unsuccessful exit of nft ends the monitor with its stderr.
+fn test_monitor_failed() {
+    let fake_nft = fake_nft(
+        "monitor-failed",
+        "echo '{\"add\": {\"table\": {\"family\": \"ip\", \"name\": \"t\", \"handle\": 1}}}'\n\
+         echo 'Error: cannot monitor' >&2\n\
+         exit 1",
+    );
+    let mut monitor = helper::monitor(fake_nft.to_str()).unwrap();
+    let event = monitor.next().unwrap();
+    let failure = monitor.next().unwrap();
+    let end = monitor.next();
+    std::fs::remove_file(&fake_nft).unwrap();
+
+    assert!(matches!(
+        event.unwrap(),
+        schema::MonitorEvent::Add(schema::NfListObject::Table(_))
+    ));
+    match failure.unwrap_err() {
+        NftablesError::NftFailed { stderr, .. } => assert_eq!("Error: cannot monitor\n", stderr),
+        other => panic!("unexpected error: {:?}", other),
+    }
+    assert!(end.is_none());
+}
+
+#[test]
+/// Displaying a failure with huge output does not print the whole output.
+fn test_nft_failed_display_truncated() {
+    let err = NftablesError::NftFailed {
+        program: "nft".to_string(),
+        hint: "applying ruleset".to_string(),
+        stdout: String::new(),
+        stderr: "Error: No such file or directory\n".repeat(100_000),
+    };
+    let displayed = format!("{}", err);
+    assert!(displayed.len() < 5000);
+    assert!(displayed.starts_with("nft did not return successfully while applying ruleset"));
+    assert!(displayed.ends_with('…'));
+    if let NftablesError::NftFailed { stderr, .. } = err {
+        assert_eq!(3_300_000, stderr.len());
+    }
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// Applies a ruleset file in native nft syntax and reads back the result.
+fn test_apply_native_file() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let applied =
+        helper::apply_native_file_and_list("resources/test/nft/native-basic.nft", None).unwrap();
+    assert!(applied.objects.iter().any(|obj| matches!(
+        obj,
+        schema::NfObject::ListObject(obj)
+            if matches!(obj.as_ref(), schema::NfListObject::Chain(chain) if chain.name == "input")
+    )));
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
+/// Records applied payloads and answers list requests with a fixed ruleset.
+struct MockTransport {
+    applied: RefCell<Vec<String>>,
+    ruleset: String,
+}
+
+impl RulesetTransport for MockTransport {
+    fn apply(&self, json: &str) -> Result<String, NftablesError> {
+        self.applied.borrow_mut().push(json.to_string());
+        Ok(String::new())
+    }
+
+    fn list(&self, _args: Option<&[&str]>) -> Result<String, NftablesError> {
+        Ok(self.ruleset.clone())
+    }
+}
+
+#[test]
+/// Applies and lists a ruleset through a transport without running nft.
+fn test_mock_transport() {
+    let transport = MockTransport {
+        applied: RefCell::new(Vec::new()),
+        ruleset: include_str!("../resources/test/json/basic.json").to_string(),
+    };
+    let ruleset = example_ruleset(false);
+    helper::apply_ruleset_via(&transport, &ruleset).unwrap();
+    let applied = transport.applied.borrow();
+    assert_eq!(1, applied.len());
+    assert_eq!(
+        ruleset,
+        serde_json::from_str::<schema::Nftables>(&applied[0]).unwrap()
+    );
+
+    let listed = helper::get_current_ruleset_via(&transport, None).unwrap();
+    assert!(!listed.objects.is_empty());
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// Lists a set with many elements tersely, expecting the elements to be omitted.
+fn test_list_terse() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let mut batch = Batch::new();
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::IP,
+        name: "test-table-terse".to_string(),
+        ..Table::default()
+    }));
+    batch.add(schema::NfListObject::Set(schema::Set {
+        family: types::NfFamily::IP,
+        table: "test-table-terse".to_string(),
+        name: "large_set".to_string(),
+        set_type: schema::SetTypeValue::Single(schema::SetType::InetService),
+        ..schema::Set::default()
+    }));
+    batch.add(schema::NfListObject::Element(schema::Element {
+        family: types::NfFamily::IP,
+        table: "test-table-terse".to_string(),
+        name: "large_set".to_string(),
+        elem: (1..=10_000).map(expr::Expression::Number).collect(),
+    }));
+    helper::apply_ruleset(&batch.to_nftables(), None, None).unwrap();
+
+    let list_args = vec!["list", "set", "ip", "test-table-terse", "large_set"];
+    let options = helper::NftListOptions { terse: true };
+    let listed = helper::get_current_ruleset_with_options(None, Some(list_args), &options).unwrap();
+    let set = listed
+        .objects
+        .iter()
+        .find_map(|obj| match obj {
+            schema::NfObject::ListObject(obj) => match obj.as_ref() {
+                schema::NfListObject::Set(set) => Some(set),
+                _ => None,
+            },
+            _ => None,
+        })
+        .expect("listing should contain the set");
+    assert_eq!(None, set.elem);
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// Applies a ruleset whose third object is invalid and expects it to be located.
+fn test_apply_ruleset_locating_failure() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let mut batch = Batch::new();
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::IP,
+        name: "test-table-failure".to_string(),
+        ..Table::default()
+    }));
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::IP6,
+        name: "test-table-failure".to_string(),
+        ..Table::default()
+    }));
+    batch.delete(schema::NfListObject::Table(Table {
+        family: types::NfFamily::IP6,
+        name: "i-do-not-exist".to_string(),
+        ..Table::default()
+    }));
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::INet,
+        name: "test-table-failure".to_string(),
+        ..Table::default()
+    }));
+    let ruleset = batch.to_nftables();
+
+    let err = helper::apply_ruleset_locating_failure(&ruleset, None, None)
+        .expect_err("Expecting nftables error for unknown table.");
+    match err {
+        NftablesError::NftBatchFailed { index, object, .. } => {
+            assert_eq!(2, index);
+            assert_eq!(ruleset.objects[2], *object);
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// Applies a ruleset only while the ruleset is unchanged since taking its fingerprint.
+fn test_apply_if_unchanged() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let baseline = helper::get_current_ruleset_fingerprint(None).unwrap();
+    let table = |name: &str| {
+        let mut batch = Batch::new();
+        batch.add(schema::NfListObject::Table(Table {
+            family: types::NfFamily::IP,
+            name: name.to_string(),
+            ..Table::default()
+        }));
+        batch.to_nftables()
+    };
+
+    helper::apply_if_unchanged(&table("test-table-unchanged"), baseline, None, None).unwrap();
+
+    let err = helper::apply_if_unchanged(&table("test-table-stale"), baseline, None, None)
+        .expect_err("Expecting the ruleset to have changed.");
+    assert!(matches!(err, NftablesError::NftRulesetChanged { .. }));
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
+#[test]
+/// The caller's args are used for listing the ruleset as well as for applying it.
+fn test_apply_if_unchanged_args() {
+    let fake_nft = fake_nft(
+        "unchanged-args",
+        "case \"$*\" in\n\
+         \"-j -I /etc/nftables.d list ruleset\") echo '{\"nftables\": []}' ;;\n\
+         \"-I /etc/nftables.d -j -f -\") cat >/dev/null ;;\n\
+         *) echo \"$@\" >&2; exit 1 ;;\n\
+         esac",
+    );
+    let empty: schema::Nftables = "{\"nftables\": []}".parse().unwrap();
+    let result = helper::apply_if_unchanged(
+        &empty,
+        helper::ruleset_fingerprint(&empty),
+        fake_nft.to_str(),
+        Some(vec!["-I", "/etc/nftables.d"]),
+    );
+    std::fs::remove_file(&fake_nft).unwrap();
+    result.unwrap();
+}
+
+#[test]
+/// Listings differing only in the order of flags have the same fingerprint.
+fn test_ruleset_fingerprint_flag_order() {
+    let listing = |set_flags: &[&str], log_flags: &[&str]| {
+        serde_json::json!({"nftables": [
+            {"metainfo": {"version": "1.0.9", "release_name": "Old Doc Yak #3", "json_schema_version": 1}},
+            {"set": {"family": "inet", "table": "filter", "name": "blocked", "handle": 3,
+                "type": "ipv4_addr", "flags": set_flags}},
+            {"rule": {"family": "inet", "table": "filter", "chain": "input", "handle": 4,
+                "expr": [{"log": {"flags": log_flags}}]}}
+        ]})
+        .to_string()
+        .parse::<schema::Nftables>()
+        .unwrap()
+    };
+    let first = listing(
+        &["constant", "interval", "timeout", "dynamic"],
+        &[
+            "tcp sequence",
+            "tcp options",
+            "ip options",
+            "skuid",
+            "ether",
+        ],
+    );
+    let second = listing(
+        &["dynamic", "timeout", "interval", "constant"],
+        &[
+            "ether",
+            "skuid",
+            "ip options",
+            "tcp options",
+            "tcp sequence",
+        ],
+    );
+    assert_eq!(
+        helper::ruleset_fingerprint(&first),
+        helper::ruleset_fingerprint(&second)
+    );
+
+    let other = listing(&["constant", "interval"], &["ether"]);
+    assert_ne!(
+        helper::ruleset_fingerprint(&first),
+        helper::ruleset_fingerprint(&other)
+    );
+}
+
+/// Returns a batch adding a table, a chain and a rule.
+fn handles_batch() -> Batch {
+    let mut batch = Batch::new();
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::IP,
+        name: "test-table-handles".to_string(),
+        ..Table::default()
+    }));
+    batch.add(schema::NfListObject::Chain(schema::Chain {
+        family: types::NfFamily::IP,
+        table: "test-table-handles".to_string(),
+        name: "test-chain".to_string(),
+        ..schema::Chain::default()
+    }));
+    batch.add(schema::NfListObject::Rule(schema::Rule::new(
+        types::NfFamily::IP,
+        "test-table-handles",
+        "test-chain",
+        [stmt::Statement::Accept(None)],
+    )));
+    batch
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// Objects added by a batch gain the handles assigned by the kernel.
+fn test_apply_and_collect_handles() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let applied = handles_batch().apply_and_collect_handles(None).unwrap();
+    let objects = applied.to_nftables().objects;
+    assert_eq!(3, objects.len());
+    for obj in &objects {
+        let obj = obj.as_cmd().and_then(|cmd| match cmd {
+            schema::NfCmd::Add(obj) => Some(obj),
+            _ => None,
+        });
+        assert!(obj.and_then(|obj| obj.handle()).is_some(), "{:?}", obj);
+    }
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
+#[test]
+/// Handles echoed by nft are merged into the applied objects in order.
+fn test_apply_and_collect_handles_echo() {
+    let echo = r#"{"nftables": [{"metainfo": {"version": "1.0.9", "release_name": "Old Doc Yak #3", "json_schema_version": 1}},
+        {"add": {"table": {"family": "ip", "name": "test-table-handles", "handle": 7}}},
+        {"add": {"chain": {"family": "ip", "table": "test-table-handles", "name": "test-chain", "handle": 1}}},
+        {"add": {"rule": {"family": "ip", "table": "test-table-handles", "chain": "test-chain", "handle": 2, "expr": [{"accept": null}]}}}]}"#;
+    let fake_nft = fake_nft("echo", &format!("cat > /dev/null\necho '{}'", echo));
+    let applied = handles_batch().apply_and_collect_handles(Some(fake_nft.to_str().unwrap()));
+    std::fs::remove_file(&fake_nft).unwrap();
+
+    let handles: Vec<Option<u32>> = applied
+        .unwrap()
+        .to_nftables()
+        .objects
+        .iter()
+        .map(|obj| match obj.as_cmd() {
+            Some(schema::NfCmd::Add(obj)) => obj.handle(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(vec![Some(7), Some(1), Some(2)], handles);
+}
+
+#[test]
+/// Handles are matched by identity, so missing or reordered echoes assign no wrong handles.
+fn test_apply_and_collect_handles_missing_echo() {
+    let mut batch = handles_batch();
+    batch.add(schema::NfListObject::Rule(schema::Rule::new(
+        types::NfFamily::IP,
+        "test-table-handles",
+        "test-chain",
+        [stmt::Statement::Drop(None)],
+    )));
+    // The chain is not echoed, and the rules are echoed before the table.
+    let echo = r#"{"nftables": [
+        {"add": {"rule": {"family": "ip", "table": "test-table-handles", "chain": "test-chain", "handle": 2, "expr": [{"accept": null}]}}},
+        {"add": {"rule": {"family": "ip", "table": "test-table-handles", "chain": "test-chain", "handle": 3, "expr": [{"drop": null}]}}},
+        {"add": {"table": {"family": "ip", "name": "test-table-handles", "handle": 7}}}]}"#;
+    let fake_nft = fake_nft("missing-echo", &format!("cat > /dev/null\necho '{}'", echo));
+    let applied = batch.apply_and_collect_handles(Some(fake_nft.to_str().unwrap()));
+    std::fs::remove_file(&fake_nft).unwrap();
+
+    let handles: Vec<Option<u32>> = applied
+        .unwrap()
+        .to_nftables()
+        .objects
+        .iter()
+        .map(|obj| match obj.as_cmd() {
+            Some(schema::NfCmd::Add(obj)) => obj.handle(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(vec![Some(7), None, Some(2), Some(3)], handles);
+}
+
+#[test]
+#[serial]
+/// The `NFT_BIN` environment variable selects the nft binary, unless a program is given explicitly.
+fn test_nft_bin_env() {
+    let program_of = |err| match err {
+        NftablesError::NftExecution { program, .. } => program,
+        other => panic!("unexpected error: {:?}", other),
+    };
+    std::env::set_var(helper::NFT_BIN_ENV, "/dev/null/nft-from-env");
+    let from_env = helper::get_current_ruleset(None, None).unwrap_err();
+    let explicit = helper::get_current_ruleset(Some("/dev/null/nft-explicit"), None).unwrap_err();
+    std::env::remove_var(helper::NFT_BIN_ENV);
+
+    assert_eq!("/dev/null/nft-from-env", program_of(from_env));
+    assert_eq!("/dev/null/nft-explicit", program_of(explicit));
+}
+
+#[test]
+/// Include directories and defines are passed to nft as `-I` and `-D` flags.
+fn test_apply_native_file_options() {
+    // fake nft, failing with its arguments on stderr
+    let fake_nft = fake_nft("native-options", "echo \"$@\" >&2\nexit 1");
+
+    let options = helper::NftNativeOptions {
+        include_dirs: vec!["/etc/nftables.d".into()],
+        defines: vec![("WAN".to_string(), "eth0".to_string())],
+    };
+    let err = helper::apply_native_file_with_options(
+        "ruleset.nft",
+        Some(fake_nft.to_str().unwrap()),
+        &options,
+    )
+    .unwrap_err();
+    std::fs::remove_file(&fake_nft).unwrap();
+
+    match err {
+        NftablesError::NftFailed { stderr, .. } => assert_eq!(
+            "-I /etc/nftables.d -D WAN=eth0 -f ruleset.nft",
+            stderr.trim_end()
+        ),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+/// Output that is not valid UTF-8 is returned as-is by `get_current_ruleset_bytes`.
+fn test_get_current_ruleset_bytes() {
+    let fake_nft = fake_nft("invalid-utf8", "printf '{\\377}'");
+    let program = Some(fake_nft.to_str().unwrap());
+    let bytes = helper::get_current_ruleset_bytes(program, None);
+    let raw = helper::get_current_ruleset_raw(program, None);
+    std::fs::remove_file(&fake_nft).unwrap();
+
+    assert_eq!(b"{\xff}".to_vec(), bytes.unwrap());
+    assert!(matches!(
+        raw.unwrap_err(),
+        NftablesError::NftOutputEncoding { .. }
+    ));
+}
+
+#[test]
+/// The pre-exec hook of `NftProcess` runs in the child before nft is executed.
+fn test_nft_process_pre_exec() {
+    let fake_nft = fake_nft("pre-exec", "echo '{\"nftables\": []}'");
+    let process = helper::NftProcess::new(fake_nft.to_str());
+    const ERANGE: i32 = 34;
+    // SAFETY: the hooks only construct values on the stack.
+    let succeeding = process
+        .clone()
+        .with_pre_exec(unsafe { helper::PreExecHook::new(|| Ok(())) });
+    let failing = process.with_pre_exec(unsafe {
+        helper::PreExecHook::new(|| Err(std::io::Error::from_raw_os_error(ERANGE)))
+    });
+    let listed = succeeding.list(None);
+    let err = failing.list(None).unwrap_err();
+    std::fs::remove_file(&fake_nft).unwrap();
+
+    assert_eq!("{\"nftables\": []}\n", listed.unwrap());
+    match err {
+        NftablesError::NftExecution { inner, .. } => {
+            assert_eq!(Some(ERANGE), inner.raw_os_error())
+        }
+        err => panic!("expected NftExecution, got {:?}", err),
+    }
+}
+
+#[test]
+/// Missing objects are recognized even if nft would report them in another language.
+fn test_object_exists_locale() {
+    let fake_nft = fake_nft(
+        "locale",
+        "if [ \"$LC_ALL\" = C ]; then\n\
+         echo 'Error: Could not process rule: No such file or directory' >&2\n\
+         else\n\
+         echo 'Error: Could not process rule: Datei oder Verzeichnis nicht gefunden' >&2\n\
+         fi\n\
+         exit 1",
+    );
+    let exists = helper::object_exists(
+        types::NfFamily::IP,
+        types::NfObjectKind::Table,
+        "missing",
+        "",
+        fake_nft.to_str(),
+    );
+    std::fs::remove_file(&fake_nft).unwrap();
+
+    assert!(!exists.unwrap());
+}
+
+/// Writes an executable shell script with the given body, to be run in place of nft.
+fn fake_nft(name: &str, body: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("fake-nft-{}-{}", name, std::process::id()));
+    std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+/// The payload passed to nft is also written to the tee, even if nft cannot be run.
+fn test_apply_ruleset_tee() {
+    let ruleset = example_ruleset(false);
+    let mut tee = Vec::new();
+    let err = helper::apply_ruleset_tee(&ruleset, &mut tee, Some("/dev/null/nft"), None)
+        .expect_err("applying the ruleset should fail with non-existing nft binary");
+    assert!(matches!(err, NftablesError::NftExecution { .. }));
+    let teed: schema::Nftables = serde_json::from_slice(&tee).unwrap();
+    assert_eq!(ruleset, teed);
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// Binds a netdev base chain to the loopback interface using the device array form.
+fn test_netdev_chain_devices() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let mut batch = Batch::new();
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::NetDev,
+        name: "test-table-netdev".to_string(),
+        ..Table::default()
+    }));
+    batch.add(schema::NfListObject::Chain(schema::Chain {
+        family: types::NfFamily::NetDev,
+        table: "test-table-netdev".to_string(),
+        name: "ingress".to_string(),
+        _type: Some(types::NfChainType::Filter),
+        hook: Some(types::NfHook::Ingress),
+        prio: Some(0),
+        dev: Some(vec!["lo".to_string()]),
+        policy: Some(types::NfChainPolicy::Accept),
+        ..schema::Chain::default()
+    }));
+    helper::apply_ruleset(&batch.to_nftables(), None, None).unwrap();
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// Checks the existence of an existing and a nonexistent table.
+fn test_object_exists_table() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let mut batch = Batch::new();
+    batch.add(schema::NfListObject::Table(Table {
+        family: types::NfFamily::IP,
+        name: "test-table-exists".to_string(),
+        ..Table::default()
+    }));
+    helper::apply_ruleset(&batch.to_nftables(), None, None).unwrap();
+
+    let exists = |table| {
+        helper::object_exists(
+            types::NfFamily::IP,
+            types::NfObjectKind::Table,
+            table,
+            table,
+            None,
+        )
+        .unwrap()
+    };
+    assert!(exists("test-table-exists"));
+    assert!(!exists("test-table-missing"));
+    flush_ruleset().expect("failed to flush ruleset");
+}
+
+#[test]
+#[ignore]
+#[serial]
+/// A chain in a nonexistent table does not exist either.
+fn test_object_exists_chain_missing_table() {
+    flush_ruleset().expect("failed to flush ruleset");
+    let exists = helper::object_exists(
+        types::NfFamily::INet,
+        types::NfObjectKind::Chain,
+        "test-table-missing",
+        "input",
+        None,
+    )
+    .unwrap();
+    assert!(!exists);
+}
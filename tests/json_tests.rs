@@ -1,7 +1,12 @@
-use nftables::expr::{self, Expression, Meta, MetaKey, NamedExpression};
-use nftables::stmt::{self, Counter, Match, Operator, Queue, Statement};
+use nftables::expr::{
+    self, BinaryOperation, CTStatus, Expression, Meta, MetaKey, NamedExpression, Payload,
+    PayloadField, PayloadProtocol, TimeOfDay, Weekday,
+};
+use nftables::stmt::{self, Counter, Mangle, Match, Operator, Queue, Statement};
 use nftables::{schema::*, types::*};
 use serde_json::json;
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr};
 
 #[test]
 fn test_chain_table_rule_inet() {
@@ -67,6 +72,7 @@ fn test_flowtable() {
                 hook: Some(NfHook::Ingress),
                 prio: Some(0),
                 dev: Some(vec!["lo".to_string()]),
+                flags: None,
             }))),
             NfObject::ListObject(Box::new(NfListObject::Chain(Chain {
                 family: NfFamily::INet,
@@ -178,7 +184,7 @@ fn test_parsing_of_queue_without_flags() {
                 Statement::Match(Match {
                     left: Expression::Named(NamedExpression::Payload(
                         nftables::expr::Payload::PayloadField(nftables::expr::PayloadField {
-                            protocol: "udp".to_string(),
+                            protocol: "udp".into(),
                             field: "dport".to_string(),
                         }),
                     )),
@@ -243,6 +249,36 @@ fn test_queue_json_serialisation() {
     assert_eq!(expected_json, serde_json::to_string(&queue).unwrap());
 }
 
+#[test]
+fn test_queue_to() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain queue to 3'
+    // ```
+    let queue = Statement::Queue(Queue::to(3));
+    let json = json!({"queue":{"num":3}});
+    assert_eq!(json, serde_json::to_value(&queue).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(queue, parsed);
+
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain queue to numgen inc mod 4 offset 2'
+    // ```
+    let queue = Statement::Queue(Queue {
+        num: Expression::Named(NamedExpression::Numgen(expr::Numgen {
+            mode: expr::NgMode::Inc,
+            ng_mod: 4,
+            offset: Some(2),
+        })),
+        flags: None,
+    });
+    let json = json!({"queue":{"num":{"numgen":{"mode":"inc","mod":4,"offset":2}}}});
+    assert_eq!(json, serde_json::to_value(&queue).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(queue, parsed);
+}
+
 #[test]
 fn test_parse_payload() {
     let expected = Nftables {
@@ -254,7 +290,7 @@ fn test_parse_payload() {
                 Statement::Match(Match {
                     left: Expression::Named(NamedExpression::Payload(
                         nftables::expr::Payload::PayloadField(nftables::expr::PayloadField {
-                            protocol: "udp".to_string(),
+                            protocol: "udp".into(),
                             field: "dport".to_string(),
                         }),
                     )),
@@ -322,3 +358,1110 @@ fn test_parse_payload() {
     let parsed: Nftables = serde_json::from_value(json).unwrap();
     assert_eq!(expected, parsed);
 }
+
+#[test]
+fn test_meta_day() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain meta day "Saturday" drop'
+    // ```
+    let expected = Statement::Match(Match {
+        left: Expression::Named(NamedExpression::Meta(Meta { key: MetaKey::Day })),
+        right: Weekday::Saturday.into(),
+        op: Operator::EQ,
+    });
+    let json = json!({"match":{"left":{"meta":{"key":"day"}},"right":"Saturday","op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_meta_hour_range() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain meta hour "17:00"-"19:00" accept'
+    // ```
+    let expected = Statement::Match(Match {
+        left: Expression::Named(NamedExpression::Meta(Meta { key: MetaKey::Hour })),
+        right: TimeOfDay::range(TimeOfDay::new(17, 0), TimeOfDay::new(19, 0)),
+        op: Operator::EQ,
+    });
+    let json = json!({"match":{"left":{"meta":{"key":"hour"}},
+        "right":{"range":["17:00","19:00"]},"op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_ct_status() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain ct status dnat accept'
+    // ```
+    let expected = Statement::Match(Match::ct_status([CTStatus::Dnat]));
+    let json = json!({"match":{"left":{"ct":{"key":"status"}},"right":"dnat","op":"in"}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    let Statement::Match(parsed) = parsed else {
+        panic!("expected match statement");
+    };
+    assert_eq!(
+        Some(HashSet::from([CTStatus::Dnat])),
+        CTStatus::from_expression(&parsed.right)
+    );
+    let multiple = CTStatus::to_expression([CTStatus::Snat, CTStatus::Dnat]);
+    assert_eq!(
+        json!(["snat", "dnat"]),
+        serde_json::to_value(&multiple).unwrap()
+    );
+    assert_eq!(
+        Some(HashSet::from([CTStatus::Snat, CTStatus::Dnat])),
+        CTStatus::from_expression(&multiple)
+    );
+}
+
+#[test]
+fn test_meta_secmark_set() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain meta secmark set "sshtag"'
+    // ```
+    let expected = Statement::Mangle(Mangle::secmark("sshtag"));
+    let json = json!({"mangle":{"key":{"meta":{"key":"secmark"}},"value":"sshtag"}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_reset_set_counters() {
+    // Equivalent nft command:
+    // ```
+    // nft 'reset set inet some_inet_table blocked'
+    // ```
+    let expected = Nftables {
+        objects: vec![NfObject::CmdObject(NfCmd::Reset(ResetObject::Set(Set {
+            family: NfFamily::INet,
+            table: "some_inet_table".to_string(),
+            name: "blocked".to_string(),
+            ..Set::default()
+        })))],
+    };
+    let json = json!({"nftables":[{"reset":{"set":{
+        "family":"inet","table":"some_inet_table","name":"blocked","type":"ipv4_addr"}}}]});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Nftables = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_binary_operation_builder() {
+    // Equivalent nft expression: `(ip dscp & 0x3c) >> 2`
+    let dscp = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+        PayloadField {
+            protocol: "ip".into(),
+            field: "dscp".to_string(),
+        },
+    )));
+    let expected = Expression::BinaryOperation(BinaryOperation::RSHIFT(
+        Box::new(Expression::BinaryOperation(BinaryOperation::AND(
+            Box::new(dscp.clone()),
+            Box::new(Expression::Number(0x3c)),
+        ))),
+        Box::new(Expression::Number(2)),
+    ));
+    assert_eq!(expected, dscp.clone().and(0x3c).rshift(2));
+    let json = json!({">>":[{"&":[{"payload":{"protocol":"ip","field":"dscp"}},60]},2]});
+    assert_eq!(
+        json,
+        serde_json::to_value(dscp.and(0x3c).rshift(2)).unwrap()
+    );
+}
+
+#[test]
+fn test_range_from_inclusive() {
+    // Equivalent nft expression: `1024-65535`
+    let expected = Expression::Range(expr::Range {
+        range: vec![Expression::Number(1024), Expression::Number(65535)],
+    });
+    assert_eq!(expected, Expression::from(1024..=65535));
+    assert_eq!(expected, Expression::Range(expr::Range::new(1024, 65535)));
+    assert_eq!(
+        json!({"range":[1024,65535]}),
+        serde_json::to_value(Expression::from(1024..=65535)).unwrap()
+    );
+}
+
+#[test]
+fn test_ct_expectation_reference() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain ct expectation set "e_pgsql"'
+    // ```
+    let expected = Statement::ct_expectation("e_pgsql");
+    let json = json!({"ct expectation":"e_pgsql"});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_tcp_option_maxseg_size() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain tcp option maxseg size 1460'
+    // ```
+    let expected = Statement::Match(Match {
+        left: Expression::Named(NamedExpression::TcpOption(expr::TcpOption::new(
+            "maxseg", "size",
+        ))),
+        right: Expression::Number(1460),
+        op: Operator::EQ,
+    });
+    let json = json!({"match":{"left":{"tcp option":{"name":"maxseg","field":"size"}},
+        "right":1460,"op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_ct_label() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain ct label foo'
+    // ```
+    let expected = Statement::Match(Match::ct_label("foo"));
+    let json = json!({"match":{"left":{"ct":{"key":"label"}},"right":"foo","op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_operator_lt_gt() {
+    // Equivalent nft commands:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain tcp dport < 1024 accept'
+    // nft 'add rule inet some_inet_table some_inet_chain tcp dport > 1024 drop'
+    // ```
+    let dport = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+        PayloadField {
+            protocol: "tcp".into(),
+            field: "dport".to_string(),
+        },
+    )));
+    for (op, symbol) in [(Operator::LT, "<"), (Operator::GT, ">")] {
+        let expected = Statement::Match(Match {
+            left: dport.clone(),
+            right: Expression::Number(1024),
+            op,
+        });
+        let json = json!({"match":{"left":{"payload":{"protocol":"tcp","field":"dport"}},
+            "right":1024,"op":symbol}});
+        assert_eq!(json, serde_json::to_value(&expected).unwrap());
+        let parsed: Statement = serde_json::from_value(json).unwrap();
+        assert_eq!(expected, parsed);
+    }
+}
+
+#[test]
+fn test_ct_bytes_64bit() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain ct bytes >= 10000000000 drop'
+    // ```
+    let expected = Statement::Match(Match {
+        left: Expression::Named(NamedExpression::CT(expr::CT::new(expr::CTKey::Bytes))),
+        right: Expression::Number(10_000_000_000),
+        op: Operator::GEQ,
+    });
+    let json =
+        json!({"match":{"left":{"ct":{"key":"bytes"}},"right":10_000_000_000_u64,"op":">="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let serialized = serde_json::to_string(&expected).unwrap();
+    assert!(serialized.contains("10000000000"));
+    let parsed: Statement = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_ct_helper_match() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain ct helper "ftp" accept'
+    // ```
+    let expected = Statement::Match(Match::ct_helper("ftp"));
+    let json = json!({"match":{"left":{"ct":{"key":"helper"}},"right":"ftp","op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_ct_expiration_match() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain ct expiration <= 30s drop'
+    // ```
+    let expected = Statement::Match(Match::ct_expiration(Operator::LEQ, 30, NfTimeUnit::Second));
+    let json = json!({"match":{"left":{"ct":{"key":"expiration"}},"right":30,"op":"<="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    assert_eq!(
+        Expression::Number(120),
+        Expression::time(2, NfTimeUnit::Minute)
+    );
+}
+
+#[test]
+/// Time values overflowing `u64` seconds are clamped instead of panicking or wrapping.
+fn test_time_overflow() {
+    assert_eq!(
+        Expression::Number(u64::MAX),
+        Expression::time(u64::MAX, NfTimeUnit::Day)
+    );
+    assert_eq!(
+        Expression::Number(u64::MAX),
+        Expression::time(u64::MAX / 60 + 1, NfTimeUnit::Minute)
+    );
+    assert_eq!(
+        Expression::Number(u64::MAX / 60 * 60),
+        Expression::time(u64::MAX / 60, NfTimeUnit::Minute)
+    );
+}
+
+#[test]
+fn test_mangle_mark_bitwise() {
+    let mark = json!({"meta": {"key": "mark"}});
+    for (mangle, op) in [
+        // nft 'add rule ip some_table some_chain meta mark set meta mark | 0x10'
+        (Mangle::set_mark_or(MetaKey::Mark, 0x10), "|"),
+        // nft 'add rule ip some_table some_chain meta mark set meta mark & 0x10'
+        (Mangle::set_mark_and(MetaKey::Mark, 0x10), "&"),
+        // nft 'add rule ip some_table some_chain meta mark set meta mark ^ 0x10'
+        (Mangle::set_mark_xor(MetaKey::Mark, 0x10), "^"),
+    ] {
+        let expected = Statement::Mangle(mangle);
+        let json = json!({"mangle": {"key": mark, "value": {op: [mark, 16]}}});
+        assert_eq!(json, serde_json::to_value(&expected).unwrap());
+        let parsed: Statement = serde_json::from_value(json).unwrap();
+        assert_eq!(expected, parsed);
+    }
+}
+
+#[test]
+fn test_cgroup_match() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain meta cgroup 0x00100001 accept'
+    // ```
+    let expected = Statement::Match(Match::meta_cgroup(0x00100001));
+    let json = json!({"match":{"left":{"meta":{"key":"cgroup"}},"right":1048577,"op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain socket cgroupv2 level 2 "system.slice/sshd.service" accept'
+    // ```
+    let expected = Statement::Match(Match::socket_cgroupv2("system.slice/sshd.service"));
+    let json = json!({"match":{"left":{"socket":{"key":"cgroupv2","level":2}},
+        "right":"system.slice/sshd.service","op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_iifname_wildcard() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain iifname "eth*" accept'
+    // ```
+    let iifname = |name: &str| {
+        Statement::Match(Match {
+            left: Expression::Named(NamedExpression::Meta(Meta {
+                key: MetaKey::Iifname,
+            })),
+            right: Expression::String(name.to_string()),
+            op: Operator::EQ,
+        })
+    };
+    let expected = iifname("eth*");
+    let json = json!({"match":{"left":{"meta":{"key":"iifname"}},"right":"eth*","op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // quotes and backslashes are escaped in JSON and preserved
+    let expected = iifname(r#"we"ird\*"#);
+    let text = serde_json::to_string(&expected).unwrap();
+    assert!(text.contains(r#""right":"we\"ird\\*""#), "{}", text);
+    assert_eq!(expected, serde_json::from_str::<Statement>(&text).unwrap());
+}
+
+#[test]
+fn test_iifname_set_wildcard() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain iifname { "eth0", "wlan*" } accept'
+    // ```
+    let expected = Statement::Match(Match::iifname(["eth0", "wlan*"]));
+    let json = json!({"match":{"left":{"meta":{"key":"iifname"}},
+        "right":{"set":["eth0","wlan*"]},"op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    let expected = Statement::Match(Match::oifname(vec!["wg*".to_string()]));
+    let json =
+        json!({"match":{"left":{"meta":{"key":"oifname"}},"right":{"set":["wg*"]},"op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+}
+
+#[test]
+fn test_policy_routing() {
+    // Equivalent nft commands:
+    // ```
+    // nft 'add rule ip some_table prerouting fib saddr . iif oif missing drop'
+    // nft 'add rule ip some_table prerouting fib daddr type local accept'
+    // nft 'add rule ip some_table prerouting rt ipsec missing rt ip nexthop 192.168.0.1 accept'
+    // nft 'add rule ip some_table prerouting fib daddr oif exists accept'
+    // ```
+    let rules = json!({"nftables": [
+        {"rule": {"family": "ip", "table": "some_table", "chain": "prerouting", "expr": [
+            {"match": {"op": "==", "left": {"fib": {"result": "oif", "flags": ["saddr", "iif"]}}, "right": false}},
+            {"drop": null}
+        ]}},
+        {"rule": {"family": "ip", "table": "some_table", "chain": "prerouting", "expr": [
+            {"match": {"op": "==", "left": {"fib": {"result": "type", "flags": "daddr"}}, "right": "local"}},
+            {"accept": null}
+        ]}},
+        {"rule": {"family": "ip", "table": "some_table", "chain": "prerouting", "expr": [
+            {"match": {"op": "==", "left": {"rt": {"key": "ipsec"}}, "right": false}},
+            {"match": {"op": "==", "left": {"rt": {"key": "nexthop", "family": "ip"}}, "right": "192.168.0.1"}},
+            {"accept": null}
+        ]}},
+        {"rule": {"family": "ip", "table": "some_table", "chain": "prerouting", "expr": [
+            {"match": {"op": "==", "left": {"fib": {"result": "oif", "flags": ["daddr", "present"]}}, "right": true}},
+            {"accept": null}
+        ]}}
+    ]});
+    let nftables: Nftables = serde_json::from_value(rules).unwrap();
+    let lefts: Vec<&Expression> = nftables
+        .objects
+        .iter()
+        .filter_map(|obj| obj.as_list_object()?.as_rule())
+        .flat_map(|rule| &rule.expr)
+        .filter_map(|stmt| match stmt {
+            Statement::Match(m) => Some(&m.left),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            &Expression::Named(NamedExpression::Fib(expr::Fib {
+                result: expr::FibResult::Oif,
+                flags: HashSet::from([expr::FibFlag::Saddr, expr::FibFlag::Iif]),
+            })),
+            &Expression::Named(NamedExpression::Fib(expr::Fib {
+                result: expr::FibResult::Type,
+                flags: HashSet::from([expr::FibFlag::Daddr]),
+            })),
+            &Expression::Named(NamedExpression::RT(expr::RT {
+                key: expr::RTKey::Ipsec,
+                family: None,
+            })),
+            &Expression::Named(NamedExpression::RT(expr::RT {
+                key: expr::RTKey::NextHop,
+                family: Some(expr::RTFamily::IP),
+            })),
+            &Expression::Named(NamedExpression::Fib(expr::Fib {
+                result: expr::FibResult::Oif,
+                flags: HashSet::from([expr::FibFlag::Daddr, expr::FibFlag::Present]),
+            })),
+        ],
+        lefts
+    );
+}
+
+#[test]
+fn test_tproxy_port_map() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule ip some_table prerouting tproxy to :tcp dport map { 80 : 8080, 443 : 8443 }'
+    // ```
+    let expected = Statement::TProxy(stmt::TProxy {
+        port: Expression::Named(NamedExpression::Map(Box::new(expr::Map::anonymous(
+            Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+                PayloadField::new(PayloadProtocol::Tcp, "dport"),
+            ))),
+            [(80.into(), 8080.into()), (443.into(), 8443.into())],
+        )))),
+        ..stmt::TProxy::to_port(0)
+    });
+    let json = json!({"tproxy": {"port": {"map": {
+        "key": {"payload": {"protocol": "tcp", "field": "dport"}},
+        "data": {"set": [[80, 8080], [443, 8443]]}}}}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule ip some_table prerouting tproxy to :8080'
+    // ```
+    assert_eq!(
+        json!({"tproxy": {"port": 8080}}),
+        serde_json::to_value(Statement::TProxy(stmt::TProxy::to_port(8080))).unwrap()
+    );
+}
+
+#[test]
+fn test_prefix_set_match() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule ip some_table some_chain ip saddr { 10.0.0.0/8, 192.168.0.0/16 } accept'
+    // ```
+    let expected = Statement::Match(Match {
+        left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+            PayloadField::new(PayloadProtocol::Ip, "saddr"),
+        ))),
+        right: Expression::prefix_set([
+            (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)), 8),
+            (IpAddr::V4(Ipv4Addr::new(192, 168, 0, 0)), 16),
+        ]),
+        op: Operator::EQ,
+    });
+    let json = json!({"match": {"op": "==",
+    "left": {"payload": {"protocol": "ip", "field": "saddr"}},
+    "right": {"set": [
+        {"prefix": {"addr": "10.0.0.0", "len": 8}},
+        {"prefix": {"addr": "192.168.0.0", "len": 16}}
+    ]}}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_set_element_comments() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add element ip some_table blocked { 1.2.3.4 comment "x", 5.6.7.8 timeout 1h }'
+    // ```
+    let json = json!({"set": {"family": "ip", "table": "some_table", "name": "blocked",
+    "type": "ipv4_addr", "flags": ["timeout"], "elem": [
+        {"elem": {"val": "1.2.3.4", "comment": "x"}},
+        {"elem": {"val": "5.6.7.8", "timeout": 3600}}
+    ]}});
+    let parsed: NfListObject = serde_json::from_value(json.clone()).unwrap();
+    let elems = parsed.as_set().unwrap().elem.as_ref().unwrap();
+    let comment = match &elems[0] {
+        Expression::Named(NamedExpression::Elem(elem)) => elem.comment.as_deref(),
+        _ => None,
+    };
+    assert_eq!(Some("x"), comment);
+    assert_eq!(json, serde_json::to_value(&parsed).unwrap());
+}
+
+#[test]
+fn test_quota_reference_or_anonymous() {
+    // nft 'add rule ip some_table some_chain quota name "myquota"'
+    let parsed: Statement = serde_json::from_value(json!({"quota": "myquota"})).unwrap();
+    assert_eq!(
+        Statement::Quota(stmt::QuotaOrQuotaRef::QuotaRef("myquota".to_string())),
+        parsed
+    );
+
+    // nft 'add rule ip some_table some_chain quota 1000 kbytes'
+    let json = json!({"quota": {"val": 1000, "val_unit": "kbytes"}});
+    let expected = Statement::Quota(stmt::QuotaOrQuotaRef::Quota(stmt::Quota {
+        val: 1000,
+        val_unit: "kbytes".to_string(),
+        used: None,
+        used_unit: None,
+        inv: None,
+    }));
+    assert_eq!(expected, serde_json::from_value(json.clone()).unwrap());
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+
+    // the unit defaults to bytes
+    let parsed: Statement = serde_json::from_value(json!({"quota": {"val": 1000}})).unwrap();
+    match parsed {
+        Statement::Quota(stmt::QuotaOrQuotaRef::Quota(quota)) => {
+            assert_eq!("bytes", quota.val_unit)
+        }
+        other => panic!("unexpected statement {:?}", other),
+    }
+}
+
+#[test]
+fn test_counter_reference_or_anonymous() {
+    // nft 'add rule ip some_table some_chain counter name "mycounter"'
+    let parsed: Statement = serde_json::from_value(json!({"counter": "mycounter"})).unwrap();
+    assert_eq!(
+        Statement::Counter(Counter::Named("mycounter".to_string())),
+        parsed
+    );
+
+    // nft 'add rule ip some_table some_chain counter'
+    let parsed: Statement = serde_json::from_value(json!({"counter": null})).unwrap();
+    assert_eq!(Statement::Counter(Counter::Anonymous(None)), parsed);
+
+    // nft 'add rule ip some_table some_chain counter packets 1 bytes 60'
+    let json = json!({"counter": {"packets": 1, "bytes": 60}});
+    let expected = Statement::Counter(Counter::Anonymous(Some(stmt::AnonymousCounter {
+        packets: Some(1),
+        bytes: Some(60),
+    })));
+    assert_eq!(expected, serde_json::from_value(json.clone()).unwrap());
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+}
+
+#[test]
+fn test_ct_count_statement() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain tcp dport 22 ct count over 4 reject'
+    // ```
+    let json = json!({"rule": {"family": "inet", "table": "some_inet_table", "chain": "some_inet_chain",
+    "expr": [
+        {"match": {"op": "==", "left": {"payload": {"protocol": "tcp", "field": "dport"}}, "right": 22}},
+        {"ct count": {"val": 4, "inv": true}},
+        {"reject": null}
+    ]}});
+    let parsed: NfListObject = serde_json::from_value(json.clone()).unwrap();
+    let rule = parsed.as_rule().unwrap();
+    assert_eq!(
+        Statement::CTCount(stmt::CTCount {
+            val: Expression::Number(4),
+            inv: Some(true),
+        }),
+        rule.expr[1]
+    );
+    assert_eq!(json, serde_json::to_value(&parsed).unwrap());
+
+    // nftables has no named ct count object
+    let object = json!({"ct count": {"family": "inet", "table": "some_inet_table", "name": "c"}});
+    assert!(serde_json::from_value::<NfListObject>(object).is_err());
+}
+
+#[test]
+/// Output of `nft -j list ruleset --handle` consists of list objects with handles.
+fn test_list_ruleset_with_handles() {
+    let nftables: Nftables =
+        serde_json::from_str(include_str!("../resources/test/json/list-handle.json")).unwrap();
+    assert_eq!(11, nftables.objects.len());
+    for obj in &nftables.objects {
+        let obj = match obj {
+            NfObject::ListObject(obj) => obj.as_ref(),
+            NfObject::CmdObject(cmd) => panic!("unexpected command {:?}", cmd),
+        };
+        let handle = match obj {
+            NfListObject::MetainfoObject(_) => continue,
+            NfListObject::Table(o) => o.handle,
+            NfListObject::Chain(o) => o.handle,
+            NfListObject::Rule(o) => o.handle,
+            NfListObject::Set(o) => o.handle,
+            NfListObject::Map(o) => o.handle,
+            NfListObject::Counter(o) => o.handle,
+            other => panic!("unexpected object {:?}", other),
+        };
+        assert!(handle.is_some(), "{:?} has no handle", obj);
+    }
+    let map = nftables
+        .objects
+        .iter()
+        .find_map(|obj| match obj {
+            NfObject::ListObject(obj) => match obj.as_ref() {
+                NfListObject::Map(map) => Some(map),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(SetTypeValue::Single(SetType::Verdict), map.map);
+}
+
+#[test]
+fn test_mangle_map() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain meta mark set ip dscp map { 46 : 1, 10 : 2 }'
+    // ```
+    let mark = Expression::Named(NamedExpression::Meta(Meta { key: MetaKey::Mark }));
+    let dscp = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+        PayloadField {
+            protocol: "ip".into(),
+            field: "dscp".to_string(),
+        },
+    )));
+    let expected = Statement::Mangle(Mangle::map(
+        mark,
+        dscp,
+        [
+            (Expression::Number(46), Expression::Number(1)),
+            (Expression::Number(10), Expression::Number(2)),
+        ],
+    ));
+    let json = json!({"mangle":{"key":{"meta":{"key":"mark"}},"value":{"map":{
+        "key":{"payload":{"protocol":"ip","field":"dscp"}},
+        "data":{"set":[[46,1],[10,2]]}}}}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_ct_timeout_assignment() {
+    // Equivalent nft commands:
+    // ```
+    // nft 'add ct timeout inet some_inet_table aggressive-tcp { protocol tcp; l3proto ip; policy = { established: 100 }; }'
+    // nft 'add rule inet some_inet_table some_inet_chain ct timeout set "aggressive-tcp"'
+    // ```
+    let timeout = CTTimeout {
+        family: NfFamily::INet,
+        table: "some_inet_table".to_string(),
+        name: "aggressive-tcp".to_string(),
+        protocol: Some(CTHProto::TCP),
+        state: Some("established".to_string()),
+        value: Some(100),
+        l3proto: Some("ip".to_string()),
+        ..CTTimeout::default()
+    };
+    let expected = timeout.to_statement();
+    assert_eq!(Statement::ct_timeout("aggressive-tcp"), expected);
+    let json = json!({"ct timeout":"aggressive-tcp"});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_concat_fib_meta() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain fib daddr type . meta mark { local . 1 } accept'
+    // ```
+    let expected = Expression::Named(NamedExpression::Concat(vec![
+        Expression::Named(NamedExpression::Fib(expr::Fib {
+            result: expr::FibResult::Type,
+            flags: HashSet::from([expr::FibFlag::Daddr]),
+        })),
+        Expression::Named(NamedExpression::Meta(Meta { key: MetaKey::Mark })),
+    ]));
+    let json =
+        json!({"concat":[{"fib":{"result":"type","flags":["daddr"]}},{"meta":{"key":"mark"}}]});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Expression = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // nft lists a single fib flag as plain string
+    let json =
+        json!({"concat":[{"fib":{"result":"type","flags":"daddr"}},{"meta":{"key":"mark"}}]});
+    let parsed: Expression = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_map_concatenated_values() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add map ip some_table portmap { type ipv4_addr . inet_service : ipv4_addr . inet_service;
+    //   elements = { 10.0.0.1 . 80 : 192.168.0.1 . 8080 } }'
+    // ```
+    let concat = |addr: &str, port: u64| {
+        Expression::Named(NamedExpression::Concat(vec![
+            Expression::String(addr.to_string()),
+            Expression::Number(port),
+        ]))
+    };
+    let concatenated = SetTypeValue::Concatenated(vec![SetType::Ipv4Addr, SetType::InetService]);
+    let expected = NfListObject::Map(Map {
+        family: NfFamily::IP,
+        table: "some_table".to_string(),
+        name: "portmap".to_string(),
+        set_type: concatenated.clone(),
+        map: concatenated,
+        elem: Some(vec![Expression::map_element(
+            concat("10.0.0.1", 80),
+            concat("192.168.0.1", 8080),
+        )]),
+        ..Map::default()
+    });
+    let json = json!({"map":{"family":"ip","table":"some_table","name":"portmap",
+        "type":["ipv4_addr","inet_service"],"map":["ipv4_addr","inet_service"],
+        "elem":[[{"concat":["10.0.0.1",80]},{"concat":["192.168.0.1",8080]}]]}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: NfListObject = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_jhash_concat() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule ip some_table some_chain meta mark set jhash ip saddr . ip daddr mod 2 seed 0xdeadbeef offset 100'
+    // ```
+    let ip_field = |field: &str| {
+        Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+            PayloadField {
+                protocol: "ip".into(),
+                field: field.to_string(),
+            },
+        )))
+    };
+    let expected = Expression::Named(NamedExpression::JHash(
+        expr::JHash::new(
+            2,
+            NamedExpression::Concat(vec![ip_field("saddr"), ip_field("daddr")]),
+        )
+        .seed(0xdeadbeef)
+        .offset(100),
+    ));
+    let json = json!({"jhash":{"mod":2,"offset":100,"expr":{"concat":[
+        {"payload":{"protocol":"ip","field":"saddr"}},
+        {"payload":{"protocol":"ip","field":"daddr"}}]},"seed":0xdeadbeef_u32}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Expression = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_mangle_mark_distribution() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule ip some_table some_chain meta mark set jhash ip saddr mod 4'
+    // ```
+    let saddr = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+        PayloadField {
+            protocol: "ip".into(),
+            field: "saddr".to_string(),
+        },
+    )));
+    let expected = stmt::Statement::Mangle(stmt::Mangle::mark(expr::JHash::new(4, saddr)));
+    let json = json!({"mangle":{"key":{"meta":{"key":"mark"}},"value":{"jhash":{
+        "mod":4,"expr":{"payload":{"protocol":"ip","field":"saddr"}}}}}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: stmt::Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // nft 'add rule ip some_table some_chain meta mark set numgen inc mod 4'
+    let expected =
+        stmt::Statement::Mangle(stmt::Mangle::mark(expr::Numgen::new(expr::NgMode::Inc, 4)));
+    let json = json!({"mangle":{"key":{"meta":{"key":"mark"}},"value":{"numgen":{
+        "mode":"inc","mod":4}}}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: stmt::Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_bridge_meta_keys() {
+    // Output of `nft -j list chain bridge filter forward` for
+    // ```
+    // nft 'add rule bridge filter forward meta ibrname "br0" meta obrname "br0" meta ibrpvid 10 meta ibrvproto 8021q'
+    // ```
+    let json = json!({"rule":{"family":"bridge","table":"filter","chain":"forward","handle":4,"expr":[
+        {"match":{"op":"==","left":{"meta":{"key":"ibrname"}},"right":"br0"}},
+        {"match":{"op":"==","left":{"meta":{"key":"obrname"}},"right":"br0"}},
+        {"match":{"op":"==","left":{"meta":{"key":"ibrpvid"}},"right":10}},
+        {"match":{"op":"==","left":{"meta":{"key":"ibrvproto"}},"right":"8021q"}}]}});
+    let meta_match = |key, right| {
+        Statement::Match(Match {
+            left: Expression::Named(NamedExpression::Meta(Meta { key })),
+            right,
+            op: Operator::EQ,
+        })
+    };
+    let expected = NfListObject::Rule(Rule {
+        family: NfFamily::Bridge,
+        table: "filter".to_string(),
+        chain: "forward".to_string(),
+        expr: vec![
+            meta_match(MetaKey::Ibrname, Expression::String("br0".to_string())),
+            meta_match(MetaKey::Obrname, Expression::String("br0".to_string())),
+            meta_match(MetaKey::Ibrpvid, Expression::Number(10)),
+            meta_match(MetaKey::Ibrvproto, Expression::String("8021q".to_string())),
+        ],
+        handle: Some(4),
+        ..Rule::default()
+    });
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: NfListObject = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // spellings of older nftables releases
+    for (key, expected) in [
+        ("ibriport", MetaKey::Ibrname),
+        ("ibridgename", MetaKey::Ibrname),
+        ("obriport", MetaKey::Obrname),
+        ("obridgename", MetaKey::Obrname),
+    ] {
+        let parsed: Meta = serde_json::from_value(json!({ "key": key })).unwrap();
+        assert_eq!(expected, parsed.key);
+    }
+
+    // deprecated names serialize to the current spelling
+    #[allow(deprecated)]
+    for (key, expected) in [
+        (MetaKey::Ibriport, "ibrname"),
+        (MetaKey::Ibridgename, "ibrname"),
+        (MetaKey::Obriport, "obrname"),
+        (MetaKey::Obridgename, "obrname"),
+    ] {
+        assert_eq!(json!(expected), serde_json::to_value(key).unwrap());
+    }
+}
+
+#[test]
+fn test_set_auto_merge() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add set ip some_table blocklist { type ipv4_addr; flags interval; auto-merge;
+    //   elements = { 10.0.0.0/8 } }'
+    // ```
+    let expected = NfListObject::Set(Set {
+        family: NfFamily::IP,
+        table: "some_table".to_string(),
+        name: "blocklist".to_string(),
+        flags: Some(HashSet::from([SetFlag::Interval])),
+        elem: Some(vec![Expression::Named(NamedExpression::Prefix(
+            expr::Prefix {
+                addr: Box::new(Expression::String("10.0.0.0".to_string())),
+                len: 8,
+            },
+        ))]),
+        auto_merge: Some(true),
+        ..Set::default()
+    });
+    let json = json!({"set":{"family":"ip","table":"some_table","name":"blocklist",
+        "type":"ipv4_addr","flags":["interval"],"elem":[{"prefix":{"addr":"10.0.0.0","len":8}}],
+        "auto-merge":true}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: NfListObject = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_concat_set_lookup() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain ip saddr . tcp dport @allowed accept'
+    // ```
+    let payload = |protocol: &str, field: &str| {
+        Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+            PayloadField {
+                protocol: protocol.into(),
+                field: field.to_string(),
+            },
+        )))
+    };
+    let set = Set {
+        name: "allowed".to_string(),
+        set_type: SetTypeValue::Concatenated(vec![SetType::Ipv4Addr, SetType::InetService]),
+        ..Set::default()
+    };
+    let key = Expression::concat([payload("ip", "saddr"), payload("tcp", "dport")]);
+    assert_eq!(set.set_type.arity(), key.arity());
+    let expected = Statement::Match(Match {
+        left: key,
+        right: Expression::String("@allowed".to_string()),
+        op: Operator::EQ,
+    });
+    let json = json!({"match":{"left":{"concat":[
+        {"payload":{"protocol":"ip","field":"saddr"}},
+        {"payload":{"protocol":"tcp","field":"dport"}}]},
+        "right":"@allowed","op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_netdev_chain_devices() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add chain netdev some_netdev_table ingress_chain { type filter hook ingress devices = { lo, eth0 } priority 0; policy accept; }'
+    // ```
+    let expected = NfListObject::Chain(Chain {
+        family: NfFamily::NetDev,
+        table: "some_netdev_table".to_string(),
+        name: "ingress_chain".to_string(),
+        _type: Some(NfChainType::Filter),
+        hook: Some(NfHook::Ingress),
+        prio: Some(0),
+        dev: Some(vec!["lo".to_string(), "eth0".to_string()]),
+        policy: Some(NfChainPolicy::Accept),
+        ..Chain::default()
+    });
+    let json = json!({"chain":{"family":"netdev","table":"some_netdev_table","name":"ingress_chain",
+        "type":"filter","hook":"ingress","prio":0,"dev":["lo","eth0"],"policy":"accept"}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: NfListObject = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // nft lists chains bound to a single device with a plain string
+    let json = json!({"chain":{"family":"netdev","table":"some_netdev_table","name":"ingress_chain",
+        "type":"filter","hook":"ingress","prio":0,"dev":"lo","policy":"accept"}});
+    let parsed: NfListObject = serde_json::from_value(json).unwrap();
+    match parsed {
+        NfListObject::Chain(chain) => assert_eq!(Some(vec!["lo".to_string()]), chain.dev),
+        other => panic!("unexpected object: {:?}", other),
+    }
+}
+
+#[test]
+fn test_fib_exists_boolean() {
+    // Equivalent nft commands:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain fib saddr . iif oif exists accept'
+    // nft 'add rule inet some_inet_table some_inet_chain fib saddr . iif oif missing drop'
+    // ```
+    for exists in [true, false] {
+        let expected = Statement::Match(Match {
+            left: Expression::Named(NamedExpression::Fib(expr::Fib {
+                result: expr::FibResult::Oif,
+                flags: HashSet::from([expr::FibFlag::Saddr, expr::FibFlag::Iif]),
+            })),
+            right: Expression::Boolean(exists),
+            op: Operator::EQ,
+        });
+        let mut json = serde_json::to_value(&expected).unwrap();
+        assert_eq!(json!(exists), json["match"]["right"]);
+        json["match"]["left"]["fib"]["flags"]
+            .as_array_mut()
+            .unwrap()
+            .sort_by_key(|flag| flag.to_string());
+        assert_eq!(
+            json!({"match":{"op":"==","left":{"fib":{"result":"oif","flags":["iif","saddr"]}},"right":exists}}),
+            json
+        );
+        let parsed: Statement = serde_json::from_value(json).unwrap();
+        assert_eq!(expected, parsed);
+    }
+}
+
+#[test]
+fn test_payload_protocol_typed() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule inet some_inet_table some_inet_chain tcp dport 22 accept'
+    // ```
+    let expected = Statement::Match(Match {
+        left: Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+            PayloadField::new(expr::PayloadProtocol::Tcp, "dport"),
+        ))),
+        right: Expression::Number(22),
+        op: Operator::EQ,
+    });
+    let json = json!({"match":{"left":{"payload":{"protocol":"tcp","field":"dport"}},"right":22,"op":"=="}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+
+    // protocols without variant are kept by name
+    let json = json!({"payload":{"protocol":"gre","field":"protocol"}});
+    let parsed: Expression = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(
+        Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+            PayloadField::new(expr::PayloadProtocol::Other("gre".to_string()), "protocol"),
+        ))),
+        parsed
+    );
+    assert_eq!(json, serde_json::to_value(&parsed).unwrap());
+    assert_eq!(expr::PayloadProtocol::Tcp, "tcp".into());
+    assert_eq!(
+        "gre",
+        expr::PayloadProtocol::Other("gre".to_string()).to_string()
+    );
+}
+
+#[test]
+/// Known protocol names never end up in `PayloadProtocol::Other`.
+fn test_payload_protocol_normalized() {
+    for name in ["ether", "ip", "ip6", "icmpv6", "udplite", "tcp", "th"] {
+        let from_str = expr::PayloadProtocol::from(name);
+        let from_string = expr::PayloadProtocol::from(name.to_string());
+        let parsed: expr::PayloadProtocol = serde_json::from_value(json!(name)).unwrap();
+        assert!(
+            !matches!(from_str, expr::PayloadProtocol::Other(_)),
+            "{}",
+            name
+        );
+        assert_eq!(from_str, from_string);
+        assert_eq!(from_str, parsed);
+        assert_eq!(name, from_str.to_string());
+        assert_eq!(json!(name), serde_json::to_value(&parsed).unwrap());
+    }
+    assert_eq!(
+        expr::PayloadProtocol::Other("other".to_string()),
+        "other".into()
+    );
+}
+
+#[test]
+fn test_map_lookup_and_update() {
+    let saddr = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+        PayloadField::new(expr::PayloadProtocol::Ip, "saddr"),
+    )));
+
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule ip some_table some_chain meta mark set ip saddr map @marks'
+    // ```
+    let expected = Statement::Mangle(Mangle::meta(
+        MetaKey::Mark,
+        Expression::map_lookup(saddr.clone(), "marks"),
+    ));
+    let json = json!({"mangle":{"key":{"meta":{"key":"mark"}},"value":{"map":{
+        "key":{"payload":{"protocol":"ip","field":"saddr"}},"data":"@marks"}}}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+    assert_eq!(
+        Expression::map_lookup(saddr.clone(), "@marks"),
+        Expression::map_lookup(saddr.clone(), "marks")
+    );
+
+    // Equivalent nft command:
+    // ```
+    // nft 'add rule ip some_table some_chain map add @marks { ip saddr : 1 }'
+    // ```
+    let expected = Statement::Map(stmt::MapUpdate {
+        op: stmt::SetOp::Add,
+        elem: saddr,
+        data: Expression::Number(1),
+        map: "@marks".to_string(),
+    });
+    let json = json!({"map":{"op":"add","elem":{"payload":{"protocol":"ip","field":"saddr"}},
+        "data":1,"map":"@marks"}});
+    assert_eq!(json, serde_json::to_value(&expected).unwrap());
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    assert_eq!(expected, parsed);
+}
+
+#[test]
+fn test_log_single_flag() {
+    // nft 'add rule inet some_table some_chain log flags tcp sequence'
+    let json = json!({"log":{"flags":"tcp sequence"}});
+    let parsed: Statement = serde_json::from_value(json).unwrap();
+    match parsed {
+        Statement::Log(Some(log)) => {
+            assert_eq!(Some(HashSet::from([stmt::LogFlag::TCPSequence])), log.flags)
+        }
+        other => panic!("expected log statement, got {:?}", other),
+    }
+
+    let invalid = json!({"log":{"flags":"tcp sequenc"}});
+    assert!(serde_json::from_value::<Statement>(invalid).is_err());
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use nftables::{batch::Batch, helper, schema::NfListObject, schema::Table};
+
+/// Collects all debug messages logged by this crate.
+struct CaptureLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.messages
+                .lock()
+                .unwrap()
+                .push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    messages: Mutex::new(Vec::new()),
+};
+
+#[test]
+/// The payload passed to nft is logged, even if nft cannot be executed.
+fn test_payload_logged() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Debug);
+
+    let mut batch = Batch::new();
+    batch.add(NfListObject::Table(Table {
+        name: "logged-table".to_string(),
+        ..Table::default()
+    }));
+    let ruleset = batch.to_nftables();
+    let _ = helper::apply_ruleset(&ruleset, Some("/dev/null/nft"), None);
+
+    let payload = serde_json::to_string(&ruleset).unwrap();
+    let messages = LOGGER.messages.lock().unwrap();
+    assert!(messages
+        .iter()
+        .any(|m| m.starts_with("running /dev/null/nft") && m.ends_with(&payload)));
+}
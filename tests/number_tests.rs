@@ -0,0 +1,70 @@
+//! Number handling must not depend on serde_json's `arbitrary_precision` feature,
+//! which may be enabled by other crates in the dependency graph.
+//!
+//! CI runs these tests with and without `--features serde_json/arbitrary_precision`.
+
+use nftables::{
+    expr::{CTKey, Expression, NamedExpression, Range, CT},
+    schema::{NfListObject, NfObject, Nftables, Set, SetType, SetTypeValue},
+    stmt::{AnonymousCounter, Counter, Match, Operator, Statement},
+    types::NfFamily,
+};
+
+#[test]
+/// Numbers within untagged enums are parsed from JSON text.
+fn test_numbers_in_untagged_enums() {
+    let statement: Statement = serde_json::from_str(
+        r#"{"match": {"left": {"ct": {"key": "bytes"}}, "right": 10000000000, "op": ">="}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        Statement::Match(Match {
+            left: Expression::Named(NamedExpression::CT(CT::new(CTKey::Bytes))),
+            right: Expression::Number(10_000_000_000),
+            op: Operator::GEQ,
+        }),
+        statement
+    );
+
+    let statement: Statement =
+        serde_json::from_str(r#"{"counter": {"packets": 1, "bytes": 4294967296}}"#).unwrap();
+    assert_eq!(
+        Statement::Counter(Counter::Anonymous(Some(AnonymousCounter {
+            packets: Some(1),
+            bytes: Some(4_294_967_296),
+        }))),
+        statement
+    );
+
+    let range: Expression = serde_json::from_str(r#"{"range": [1024, 65535]}"#).unwrap();
+    assert_eq!(Expression::Range(Range::new(1024, 65535)), range);
+}
+
+#[test]
+/// Numeric properties of listed objects, e.g. handles and sizes, are parsed from JSON text.
+fn test_numbers_in_listed_objects() {
+    let nftables: Nftables = serde_json::from_str(
+        r#"{"nftables": [{"set": {"family": "ip", "table": "filter", "name": "ports",
+            "handle": 4, "type": "inet_service", "size": 65535, "elem": [22, 80, 443]}}]}"#,
+    )
+    .unwrap();
+    let set = nftables.objects[0].clone();
+    let expected = NfListObject::Set(Set {
+        family: NfFamily::IP,
+        table: "filter".to_string(),
+        name: "ports".to_string(),
+        handle: Some(4),
+        set_type: SetTypeValue::Single(SetType::InetService),
+        size: Some(65535),
+        elem: Some(vec![22.into(), 80.into(), 443.into()]),
+        ..Set::default()
+    });
+    assert_eq!(NfObject::ListObject(Box::new(expected)), set);
+}
+
+#[test]
+/// Numbers are serialized as plain JSON numbers.
+fn test_numbers_serialized() {
+    let json = serde_json::to_string(&Expression::Number(u64::MAX)).unwrap();
+    assert_eq!("18446744073709551615", json);
+}
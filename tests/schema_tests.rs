@@ -0,0 +1,437 @@
+use std::collections::HashSet;
+
+use nftables::{
+    error::ParseError,
+    expr::Expression,
+    schema::{
+        Chain, FlowTable, FlowTableBuilder, NfCmd, NfListObject, NfObject, Nftables,
+        ObjectIdentity, Rule, Set, SynProxy, Table, TableIdentity,
+    },
+    stmt::{Counter, Statement},
+    types::{
+        NfChainPolicy, NfFamily, NfFlowTableFlag, NfHook, NfPriority, NfTimeUnit, SynProxyFlag,
+    },
+};
+use serde_json::json;
+
+#[test]
+fn test_rule_new() {
+    let expected = Rule {
+        family: NfFamily::IP,
+        table: "filter".to_string(),
+        chain: "input".to_string(),
+        expr: vec![
+            Statement::Counter(Counter::Anonymous(None)),
+            Statement::Drop(None),
+        ],
+        handle: None,
+        index: None,
+        comment: None,
+    };
+    let statements = [
+        Statement::Counter(Counter::Anonymous(None)),
+        Statement::Drop(None),
+    ];
+    assert_eq!(
+        expected,
+        Rule::new(NfFamily::IP, "filter", "input", statements)
+    );
+}
+
+#[test]
+fn test_flowtable_builder() {
+    // Equivalent nft command:
+    // ```
+    // nft 'add flowtable inet filter f { hook ingress priority filter; devices = { eth0 }; flags offload; counter; }'
+    // ```
+    let flowtable = FlowTableBuilder::new(NfFamily::INet, "filter", "f")
+        .named_prio(NfPriority::Filter)
+        .device("eth0")
+        .flag(NfFlowTableFlag::Offload)
+        .counter()
+        .build();
+    assert_eq!(Some(NfHook::Ingress), flowtable.hook);
+    assert_eq!(Some(0), flowtable.prio);
+    assert_eq!(Some(vec!["eth0".to_string()]), flowtable.dev);
+
+    let mut value = serde_json::to_value(&flowtable).unwrap();
+    let flags = value["flags"].as_array_mut().unwrap();
+    flags.sort_by_key(|flag| flag.to_string());
+    let expected = json!({
+        "family": "inet",
+        "table": "filter",
+        "name": "f",
+        "hook": "ingress",
+        "prio": 0,
+        "dev": ["eth0"],
+        "flags": ["counter", "offload"],
+    });
+    assert_eq!(expected, value);
+}
+
+#[test]
+fn test_flowtable_delete_reference() {
+    // Equivalent nft command:
+    // ```
+    // nft 'delete flowtable inet filter f'
+    // ```
+    let delete = NfCmd::Delete(NfListObject::FlowTable(FlowTable::reference(
+        NfFamily::INet,
+        "filter",
+        "f",
+    )));
+    assert_eq!(
+        json!({"delete": {"flowtable": {"family": "inet", "table": "filter", "name": "f"}}}),
+        serde_json::to_value(&delete).unwrap()
+    );
+
+    // unset hook and prio are omitted from complete flowtables as well
+    let flowtable = FlowTable {
+        dev: Some(vec!["eth0".to_string()]),
+        ..FlowTable::default()
+    };
+    let value = serde_json::to_value(&flowtable).unwrap();
+    assert!(value.get("hook").is_none());
+    assert!(value.get("prio").is_none());
+}
+
+#[test]
+fn test_try_from_value() {
+    let table = json!({"table": {"family": "ip", "name": "filter"}});
+    assert_eq!(
+        NfListObject::Table(Table {
+            family: NfFamily::IP,
+            name: "filter".to_string(),
+            handle: None,
+        }),
+        NfListObject::try_from(table.clone()).unwrap()
+    );
+
+    let nftables = Nftables::try_from(json!({"nftables": [table]})).unwrap();
+    assert_eq!(1, nftables.objects.len());
+
+    assert_eq!(
+        Statement::Drop(None),
+        Statement::try_from(json!({"drop": null})).unwrap()
+    );
+
+    let err = Statement::try_from(json!({"nosuchstatement": null})).unwrap_err();
+    assert!(matches!(err, ParseError::InvalidJson(_)));
+}
+
+#[test]
+/// A listed ruleset without metainfo can be used as input again.
+fn test_without_metainfo() {
+    let listed: Nftables =
+        serde_json::from_str(include_str!("../resources/test/json/basic.json")).unwrap();
+    let count = listed.objects.len();
+    let input = listed.without_metainfo();
+    assert_eq!(count - 1, input.objects.len());
+
+    let json = serde_json::to_value(&input).unwrap();
+    for obj in json["nftables"].as_array().unwrap() {
+        assert!(obj.get("metainfo").is_none());
+    }
+    assert_eq!(input, serde_json::from_value(json).unwrap());
+}
+
+#[test]
+fn test_time_unit_seconds() {
+    assert_eq!(1, NfTimeUnit::Second.as_seconds());
+    assert_eq!(3600, NfTimeUnit::Hour.as_seconds());
+    assert_eq!(604800, NfTimeUnit::Week.as_seconds());
+    assert!(NfTimeUnit::Minute < NfTimeUnit::Hour);
+    assert!(NfTimeUnit::Week > NfTimeUnit::Day);
+    assert_eq!(
+        Some(NfTimeUnit::Week),
+        [NfTimeUnit::Day, NfTimeUnit::Week, NfTimeUnit::Second]
+            .into_iter()
+            .max()
+    );
+}
+
+#[test]
+/// Listing a table with an empty chain yields no rule objects at all.
+fn test_chain_without_rules() {
+    let nftables: Nftables = serde_json::from_value(json!({"nftables": [
+        {"metainfo": {"version": "1.0.9", "release_name": "Old Doc Yak #3", "json_schema_version": 1}},
+        {"table": {"family": "ip", "name": "filter", "handle": 1}},
+        {"chain": {"family": "ip", "table": "filter", "name": "empty", "handle": 1}}
+    ]}))
+    .unwrap();
+    assert_eq!(3, nftables.objects.len());
+    assert!(matches!(
+        &nftables.objects[2],
+        NfObject::ListObject(obj) if matches!(**obj, NfListObject::Chain(_))
+    ));
+}
+
+#[test]
+/// A rule without `expr` is rejected with an error naming the missing property.
+fn test_rule_without_expr() {
+    let rule = json!({"rule": {"family": "ip", "table": "filter", "chain": "input", "handle": 3}});
+    let err =
+        serde_json::from_value::<Nftables>(json!({ "nftables": [rule.clone()] })).unwrap_err();
+    assert!(err.to_string().contains("missing field `expr`"), "{}", err);
+    let err =
+        serde_json::from_value::<Nftables>(json!({ "nftables": [{ "add": rule }] })).unwrap_err();
+    assert!(err.to_string().contains("missing field `expr`"), "{}", err);
+}
+
+#[test]
+/// Non-exhaustive enums are matched with a wildcard arm or through their accessors.
+fn test_non_exhaustive_matching() {
+    let rule = NfListObject::Rule(Rule::new(
+        NfFamily::IP,
+        "filter",
+        "input",
+        [Statement::Drop(None)],
+    ));
+    let kind = match &rule {
+        NfListObject::Table(_) => "table",
+        NfListObject::Rule(_) => "rule",
+        _ => "other",
+    };
+    assert_eq!("rule", kind);
+    assert_eq!(
+        Some("input"),
+        rule.as_rule().map(|rule| rule.chain.as_str())
+    );
+    assert_eq!(None, rule.as_table());
+
+    let expr = Expression::String("@allowed".to_string());
+    let value = match &expr {
+        Expression::String(s) => s.as_str(),
+        _ => "",
+    };
+    assert_eq!(expr.as_str(), Some(value));
+    assert_eq!(None, expr.as_number());
+    assert_eq!(None, expr.as_named());
+}
+
+#[test]
+/// A sized set is full once it holds `size` elements.
+fn test_set_element_count() {
+    let mut set = Set {
+        size: Some(3),
+        elem: Some(vec![
+            Expression::String("10.0.0.1".to_string()),
+            Expression::String("10.0.0.2".to_string()),
+        ]),
+        ..Set::default()
+    };
+    assert_eq!(2, set.element_count());
+    assert!(!set.is_full());
+
+    set.elem
+        .as_mut()
+        .unwrap()
+        .push(Expression::String("10.0.0.3".to_string()));
+    assert_eq!(3, set.element_count());
+    assert!(set.is_full());
+
+    set.size = None;
+    assert!(!set.is_full());
+    set.elem = None;
+    assert_eq!(0, set.element_count());
+}
+
+#[test]
+/// A complete named synproxy round-trips, and single-string flags are accepted.
+fn test_synproxy_roundtrip() {
+    let mut synproxy = SynProxy::new(NfFamily::INet, "filter", "backend", 1460, 7);
+    synproxy.flags = Some(HashSet::from([
+        SynProxyFlag::Timestamp,
+        SynProxyFlag::SackPerm,
+    ]));
+    let nftables = Nftables {
+        objects: vec![NfObject::ListObject(Box::new(NfListObject::SynProxy(
+            synproxy,
+        )))],
+    };
+    let json = serde_json::to_string(&nftables).unwrap();
+    assert_eq!(nftables, json.parse::<Nftables>().unwrap());
+
+    // Equivalent nft command: `add synproxy inet filter backend { mss 1460; wscale 7; timestamp; }`
+    let single: Nftables = json!({"nftables": [{"synproxy": {
+        "family": "inet",
+        "table": "filter",
+        "name": "backend",
+        "mss": 1460,
+        "wscale": 7,
+        "flags": "timestamp"
+    }}]})
+    .to_string()
+    .parse()
+    .unwrap();
+    let mut expected = SynProxy::new(NfFamily::INet, "filter", "backend", 1460, 7);
+    expected.flags = Some(HashSet::from([SynProxyFlag::Timestamp]));
+    assert_eq!(
+        NfObject::ListObject(Box::new(NfListObject::SynProxy(expected))),
+        single.objects[0]
+    );
+}
+
+#[test]
+/// A snapshot built from list objects contains no commands.
+fn test_nftables_from_objects() {
+    let snapshot = Nftables::from_objects([
+        NfListObject::Table(Table::default()),
+        NfListObject::Set(Set::default()),
+        NfListObject::Rule(Rule::new(
+            NfFamily::INet,
+            "filter",
+            "input",
+            [Statement::Accept(None)],
+        )),
+    ]);
+    assert_eq!(3, snapshot.objects.len());
+    assert!(!snapshot
+        .objects
+        .iter()
+        .any(|obj| matches!(obj, NfObject::CmdObject(_))));
+}
+
+#[test]
+/// Accessors pick out list objects and commands of a mixed document.
+fn test_nfobject_accessors() {
+    let nftables: Nftables = json!({"nftables": [
+        {"table": {"family": "ip", "name": "filter"}},
+        {"flush": {"table": {"family": "ip", "name": "filter"}}}
+    ]})
+    .to_string()
+    .parse()
+    .unwrap();
+    let (listed, cmd) = (&nftables.objects[0], &nftables.objects[1]);
+
+    assert!(!listed.is_command());
+    assert_eq!(
+        Some("filter"),
+        listed
+            .as_list_object()
+            .and_then(NfListObject::as_table)
+            .map(|t| t.name.as_str())
+    );
+    assert_eq!(None, listed.as_cmd());
+
+    assert!(cmd.is_command());
+    assert!(matches!(cmd.as_cmd(), Some(NfCmd::Flush(_))));
+    assert_eq!(None, cmd.as_list_object());
+
+    assert_eq!(
+        1,
+        nftables
+            .objects
+            .iter()
+            .filter_map(NfObject::as_list_object)
+            .count()
+    );
+}
+
+#[test]
+/// Identities key objects by family, table and name, ignoring other properties.
+fn test_identity_hashing() {
+    let filter = Table {
+        family: NfFamily::INet,
+        name: "filter".to_string(),
+        handle: None,
+    };
+    let listed = Table {
+        handle: Some(3),
+        ..filter.clone()
+    };
+    let nat = Table {
+        family: NfFamily::IP,
+        name: "nat".to_string(),
+        handle: None,
+    };
+    let tables: HashSet<TableIdentity> = [&filter, &listed, &nat]
+        .iter()
+        .map(|t| t.identity())
+        .collect();
+    assert_eq!(2, tables.len());
+    assert!(tables.contains(&(NfFamily::INet, "filter".to_string())));
+
+    // tables and chains may be hashed as a whole as well
+    assert_eq!(3, HashSet::from([filter, listed, nat]).len());
+
+    let set = Set {
+        family: NfFamily::INet,
+        table: "filter".to_string(),
+        name: "blocked".to_string(),
+        ..Set::default()
+    };
+    let chains: HashSet<ObjectIdentity> = HashSet::from([
+        Chain::default().identity(),
+        Chain {
+            policy: Some(NfChainPolicy::Drop),
+            ..Chain::default()
+        }
+        .identity(),
+    ]);
+    assert_eq!(1, chains.len());
+    assert_eq!(
+        (NfFamily::INet, "filter".to_string(), "blocked".to_string()),
+        set.identity()
+    );
+}
+
+#[test]
+/// A listed rule with an unknown log flag is rejected with an error instead of panicking.
+fn test_unknown_log_flag() {
+    let rule_with_flags = |flags: serde_json::Value| {
+        json!({"nftables": [{"rule": {
+            "family": "inet",
+            "table": "filter",
+            "chain": "input",
+            "handle": 4,
+            "expr": [{"log": {"prefix": "in: ", "flags": flags}}]
+        }}]})
+        .to_string()
+    };
+
+    let known = rule_with_flags(json!(["ip options", "skuid"])).parse::<Nftables>();
+    assert!(known.is_ok());
+    for flags in [json!("bogus"), json!(["ip options", "bogus"])] {
+        let result = rule_with_flags(flags).parse::<Nftables>();
+        assert!(matches!(result, Err(ParseError::InvalidJsonAt { .. })));
+    }
+}
+
+#[test]
+/// Errors inside a rule report the path to the rule's property.
+fn test_rule_error_path() {
+    let document = |rule: serde_json::Value| {
+        json!({"nftables": [
+            {"table": {"family": "ip", "name": "filter"}},
+            rule
+        ]})
+        .to_string()
+    };
+    let rule = json!({"rule": {"family": "ip", "table": "filter", "chain": "input"}});
+    for (document, expected_path) in [
+        (document(rule.clone()), "nftables[1].rule"),
+        (document(json!({ "add": rule })), "nftables[1].add.rule"),
+    ] {
+        match document.parse::<Nftables>() {
+            Err(ParseError::InvalidJsonAt { path, inner }) => {
+                assert_eq!(expected_path, path);
+                assert!(
+                    inner.to_string().contains("missing field `expr`"),
+                    "{}",
+                    inner
+                );
+            }
+            other => panic!("expected InvalidJsonAt, got {:?}", other),
+        }
+    }
+
+    let invalid_expr =
+        json!({"rule": {"family": "ip", "table": "filter", "chain": "input", "expr": 5}});
+    match document(invalid_expr).parse::<Nftables>() {
+        Err(ParseError::InvalidJsonAt { path, .. }) => {
+            assert_eq!("nftables[1].rule.expr", path)
+        }
+        other => panic!("expected InvalidJsonAt, got {:?}", other),
+    }
+}
@@ -1,4 +1,7 @@
-use nftables::{expr::*, schema::*, stmt::*, types::*};
+use std::collections::HashSet;
+
+use nftables::{expr::*, schema::*, stmt, stmt::*, types::*};
+use serde_json::json;
 
 #[test]
 fn test_serialize() {
@@ -52,3 +55,425 @@ fn test_serialize() {
     println!("JSON: {}", j);
     println!("Parsed: {:?}", result);
 }
+
+/// Builds one representative instance of every [Statement] variant, keyed by variant name.
+fn statement_samples() -> Vec<(&'static str, Statement)> {
+    let tcp_dport = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+        PayloadField {
+            protocol: "tcp".into(),
+            field: "dport".to_string(),
+        },
+    )));
+    let ip_saddr = Expression::Named(NamedExpression::Payload(Payload::PayloadField(
+        PayloadField {
+            protocol: "ip".into(),
+            field: "saddr".to_string(),
+        },
+    )));
+    let meta_mark = Expression::Named(NamedExpression::Meta(Meta { key: MetaKey::Mark }));
+    vec![
+        ("accept", Statement::Accept(None)),
+        ("drop", Statement::Drop(None)),
+        ("continue", Statement::Continue(None)),
+        ("return", Statement::Return(None)),
+        (
+            "jump",
+            Statement::Jump(JumpTarget {
+                target: "other_chain".to_string(),
+            }),
+        ),
+        (
+            "goto",
+            Statement::Goto(JumpTarget {
+                target: "other_chain".to_string(),
+            }),
+        ),
+        (
+            "match",
+            Statement::Match(Match {
+                left: tcp_dport.clone(),
+                right: Expression::Number(22),
+                op: Operator::EQ,
+            }),
+        ),
+        (
+            "counter",
+            Statement::Counter(stmt::Counter::Anonymous(Some(AnonymousCounter {
+                packets: Some(1),
+                bytes: Some(64),
+            }))),
+        ),
+        (
+            "mangle",
+            Statement::Mangle(Mangle {
+                key: meta_mark.clone(),
+                value: Expression::Number(42),
+            }),
+        ),
+        (
+            "quota",
+            Statement::Quota(QuotaOrQuotaRef::Quota(stmt::Quota {
+                val: 25,
+                val_unit: "mbytes".to_string(),
+                used: None,
+                used_unit: None,
+                inv: Some(true),
+            })),
+        ),
+        (
+            "limit",
+            Statement::Limit(stmt::Limit {
+                rate: 10,
+                rate_unit: Some("packets".to_string()),
+                per: Some("second".to_string()),
+                burst: Some(5),
+                burst_unit: None,
+                inv: None,
+            }),
+        ),
+        (
+            "flow",
+            Statement::Flow(Flow {
+                op: stmt::SetOp::Add,
+                flowtable: "@ft".to_string(),
+            }),
+        ),
+        (
+            "fwd",
+            Statement::FWD(Some(FWD {
+                dev: Some(Expression::String("eth0".to_string())),
+                family: None,
+                addr: None,
+            })),
+        ),
+        ("notrack", Statement::Notrack),
+        (
+            "dup",
+            Statement::Dup(Dup {
+                addr: Expression::String("10.0.0.1".to_string()),
+                dev: Some(Expression::String("eth0".to_string())),
+            }),
+        ),
+        (
+            "snat",
+            Statement::SNAT(Some(NAT {
+                addr: Some(Expression::String("10.0.0.1".to_string())),
+                family: Some(NATFamily::IP),
+                port: None,
+                flags: None,
+            })),
+        ),
+        (
+            "dnat",
+            Statement::DNAT(Some(NAT {
+                addr: Some(Expression::String("10.0.0.2".to_string())),
+                family: Some(NATFamily::IP),
+                port: Some(8080),
+                flags: Some(HashSet::from([NATFlag::Persistent])),
+            })),
+        ),
+        ("masquerade", Statement::Masquerade(None)),
+        (
+            "redirect",
+            Statement::Redirect(Some(NAT {
+                addr: None,
+                family: None,
+                port: Some(3128),
+                flags: None,
+            })),
+        ),
+        (
+            "reject",
+            Statement::Reject(Some(Reject::new(
+                Some(RejectType::ICMPX),
+                Some(RejectCode::AdminProhibited),
+            ))),
+        ),
+        (
+            "set",
+            Statement::Set(stmt::Set {
+                op: stmt::SetOp::Update,
+                elem: ip_saddr.clone(),
+                set: "@seen".to_string(),
+            }),
+        ),
+        (
+            "log",
+            Statement::Log(Some(Log {
+                prefix: Some("dropped: ".to_string()),
+                level: Some(LogLevel::Info),
+                flags: Some(HashSet::from([LogFlag::TCPSequence])),
+                ..Log::new(None)
+            })),
+        ),
+        ("ct helper", Statement::CTHelper("ftp-standard".to_string())),
+        (
+            "meter",
+            Statement::Meter(stmt::Meter {
+                name: "per_source".to_string(),
+                key: ip_saddr.clone(),
+                stmt: Box::new(Statement::Limit(stmt::Limit {
+                    rate: 10,
+                    rate_unit: None,
+                    per: Some("second".to_string()),
+                    burst: None,
+                    burst_unit: None,
+                    inv: None,
+                })),
+            }),
+        ),
+        (
+            "queue",
+            Statement::Queue(Queue {
+                num: Expression::Number(3),
+                flags: Some(HashSet::from([QueueFlag::Bypass])),
+            }),
+        ),
+        (
+            "vmap",
+            Statement::VerdictMap(VerdictMap {
+                key: tcp_dport,
+                data: Expression::Named(NamedExpression::Set(vec![SetItem::Mapping(
+                    Expression::Number(22),
+                    Expression::Verdict(Verdict::Accept),
+                )])),
+            }),
+        ),
+        (
+            "ct count",
+            Statement::CTCount(CTCount {
+                val: Expression::Number(10),
+                inv: Some(true),
+            }),
+        ),
+        (
+            "ct timeout",
+            Statement::CTTimeout(Expression::String("short".to_string())),
+        ),
+        (
+            "ct expectation",
+            Statement::CTExpectation(Expression::String("expect-ftp".to_string())),
+        ),
+        ("xt", Statement::XT(None)),
+        (
+            "synproxy",
+            Statement::SynProxy(stmt::SynProxy {
+                mss: Some(1460),
+                wscale: Some(7),
+                flags: Some(HashSet::from([SynProxyFlag::Timestamp])),
+            }),
+        ),
+        (
+            "tproxy",
+            Statement::TProxy(TProxy {
+                family: Some("ip".to_string()),
+                port: Expression::Number(1234),
+                addr: Some("127.0.0.1".to_string()),
+            }),
+        ),
+    ]
+}
+
+#[test]
+/// Serializes every statement variant and compares it to the JSON in `resources/test/golden/statements.json`.
+fn test_statement_golden() {
+    let golden: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(include_str!("../resources/test/golden/statements.json")).unwrap();
+    let samples = statement_samples();
+    assert_eq!(
+        golden.len(),
+        samples.len(),
+        "golden file and samples differ"
+    );
+    for (name, statement) in samples {
+        let expected = golden
+            .get(name)
+            .unwrap_or_else(|| panic!("no golden JSON for statement {}", name));
+        let serialized = serde_json::to_value(&statement).unwrap();
+        assert_eq!(expected, &serialized, "statement {} changed", name);
+        let parsed: Statement = serde_json::from_value(serialized).unwrap();
+        assert_eq!(statement, parsed, "statement {} does not round-trip", name);
+    }
+}
+
+#[test]
+/// Statements built from default options serialize to their minimal form.
+fn test_statement_defaults() {
+    let statements = [
+        (Statement::SNAT(Some(NAT::default())), json!({"snat": {}})),
+        (
+            Statement::Reject(Some(Reject::default())),
+            json!({"reject": {}}),
+        ),
+        (Statement::Log(Some(Log::default())), json!({"log": {}})),
+        (Statement::FWD(Some(FWD::default())), json!({"fwd": {}})),
+        (
+            Statement::SynProxy(stmt::SynProxy::default()),
+            json!({"synproxy": {}}),
+        ),
+    ];
+    for (statement, expected) in statements {
+        assert_eq!(expected, serde_json::to_value(&statement).unwrap());
+    }
+}
+
+#[test]
+fn test_synproxy_for_backend() {
+    // Equivalent nft statement: `synproxy mss 1460 wscale 7 timestamp sack-perm`
+    let statement = Statement::SynProxy(stmt::SynProxy::for_backend(1460, 7, true, true));
+    let mut value = serde_json::to_value(&statement).unwrap();
+    value["synproxy"]["flags"]
+        .as_array_mut()
+        .unwrap()
+        .sort_by_key(|flag| flag.to_string());
+    assert_eq!(
+        json!({"synproxy": {"mss": 1460, "wscale": 7, "flags": ["sack-perm", "timestamp"]}}),
+        value
+    );
+
+    // Equivalent nft statement: `synproxy mss 1440 wscale 0`
+    let statement = Statement::SynProxy(stmt::SynProxy::for_backend(1440, 0, false, false));
+    assert_eq!(
+        json!({"synproxy": {"mss": 1440, "wscale": 0}}),
+        serde_json::to_value(&statement).unwrap()
+    );
+}
+
+#[test]
+fn test_counter_verdict() {
+    // Equivalent nft statements: `counter drop` and `counter accept`
+    assert_eq!(
+        json!([{"counter": null}, {"drop": null}]),
+        serde_json::to_value(Statement::counter_drop()).unwrap()
+    );
+    assert_eq!(
+        json!([{"counter": null}, {"accept": null}]),
+        serde_json::to_value(Statement::counter_accept()).unwrap()
+    );
+
+    let rule = Rule::new(NfFamily::INet, "filter", "input", Statement::counter_drop());
+    assert_eq!(
+        vec![
+            Statement::Counter(stmt::Counter::Anonymous(None)),
+            Statement::Drop(None)
+        ],
+        rule.expr
+    );
+}
+
+#[test]
+fn test_log_verdict() {
+    // Equivalent nft statements: `log prefix "dropped: " drop`
+    let statements = Statement::log_drop("dropped: ");
+    assert_eq!(
+        json!([{"log": {"prefix": "dropped: "}}, {"drop": null}]),
+        serde_json::to_value(&statements).unwrap()
+    );
+    assert_eq!(Statement::Drop(None), statements[1]);
+
+    // Equivalent nft statements: `log prefix "rejected: " reject with tcp reset`
+    let statements = Statement::log_reject(
+        "rejected: ",
+        Some(Reject::new(Some(RejectType::TCPReset), None)),
+    );
+    assert_eq!(
+        json!([{"log": {"prefix": "rejected: "}}, {"reject": {"type": "tcp reset"}}]),
+        serde_json::to_value(&statements).unwrap()
+    );
+
+    // Equivalent nft statements: `log prefix "rejected: " reject`
+    assert_eq!(
+        json!([{"log": {"prefix": "rejected: "}}, {"reject": null}]),
+        serde_json::to_value(Statement::log_reject("rejected: ", None)).unwrap()
+    );
+}
+
+#[test]
+fn test_nat_to_socketaddr() {
+    // Equivalent nft statement: `dnat ip to 192.168.1.10:8080`
+    let addr: std::net::SocketAddr = "192.168.1.10:8080".parse().unwrap();
+    assert_eq!(
+        json!({"dnat": {"addr": "192.168.1.10", "family": "ip", "port": 8080}}),
+        serde_json::to_value(Statement::DNAT(Some(NAT::to_socketaddr(addr)))).unwrap()
+    );
+
+    // Equivalent nft statement: `dnat ip6 to [fd00::10]:443`
+    let addr: std::net::SocketAddr = "[fd00::10]:443".parse().unwrap();
+    let nat = NAT::from(addr);
+    assert_eq!(Some(NATFamily::IP6), nat.family);
+    assert_eq!(
+        json!({"dnat": {"addr": "fd00::10", "family": "ip6", "port": 443}}),
+        serde_json::to_value(Statement::DNAT(Some(nat))).unwrap()
+    );
+}
+
+#[test]
+fn test_reject_numeric_code() {
+    // Some nftables releases list `reject with icmp port-unreachable` with a numeric code.
+    let statement: Statement =
+        serde_json::from_value(json!({"reject": {"type": "icmp", "expr": 3}})).unwrap();
+    assert_eq!(
+        Statement::Reject(Some(Reject::new(
+            Some(RejectType::ICMP),
+            Some(RejectCode::PortUnreach)
+        ))),
+        statement
+    );
+
+    let statement: Statement =
+        serde_json::from_value(json!({"reject": {"type": "icmpv6", "expr": 4}})).unwrap();
+    assert_eq!(
+        Statement::Reject(Some(Reject::new(
+            Some(RejectType::ICMPv6),
+            Some(RejectCode::PortUnreach)
+        ))),
+        statement
+    );
+
+    // codes without symbolic name are kept
+    let json = json!({"reject": {"type": "icmp", "expr": 4}});
+    let statement: Statement = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(
+        Statement::Reject(Some(Reject::new(
+            Some(RejectType::ICMP),
+            Some(RejectCode::Other(4))
+        ))),
+        statement
+    );
+    assert_eq!(json, serde_json::to_value(&statement).unwrap());
+}
+
+#[test]
+fn test_limit_packets_without_burst_unit() {
+    // Equivalent nft statement: `limit rate 10/second burst 5 packets`
+    let limit = stmt::Limit {
+        rate: 10,
+        rate_unit: Some("packets".to_string()),
+        per: Some("second".to_string()),
+        burst: Some(5),
+        burst_unit: Some("bytes".to_string()),
+        inv: None,
+    };
+    assert_eq!(
+        json!({"limit": {"rate": 10, "rate_unit": "packets", "per": "second", "burst": 5}}),
+        serde_json::to_value(Statement::Limit(limit.clone())).unwrap()
+    );
+
+    // Equivalent nft statement: `limit rate 1 mbytes/second burst 512 kbytes`
+    let limit = stmt::Limit {
+        rate_unit: Some("mbytes".to_string()),
+        burst: Some(512),
+        burst_unit: Some("kbytes".to_string()),
+        rate: 1,
+        ..limit
+    };
+    let json = json!({"limit": {"rate": 1, "rate_unit": "mbytes", "per": "second", "burst": 512, "burst_unit": "kbytes"}});
+    assert_eq!(
+        json,
+        serde_json::to_value(Statement::Limit(limit.clone())).unwrap()
+    );
+    assert_eq!(
+        Statement::Limit(limit),
+        serde_json::from_value::<Statement>(json).unwrap()
+    );
+}
@@ -0,0 +1,306 @@
+use std::collections::HashSet;
+
+use nftables::{
+    expr::{Expression, PayloadField, TcpOption},
+    schema::{Chain, Element, Rule, Set, SetFlag, SetType, SetTypeValue, SynProxy},
+    stmt::{Limit, Log, LogLevel, NATFamily, Queue, Statement, NAT},
+    types::{NfChainPolicy, NfChainType, NfFamily, NfHook},
+    validation::ValidationError,
+};
+
+#[test]
+/// A concatenation of two set types is valid.
+fn test_set_type_concatenated() {
+    let set = Set {
+        set_type: SetTypeValue::Concatenated(vec![SetType::Ipv4Addr, SetType::InetService]),
+        ..Set::default()
+    };
+    assert_eq!(2, set.set_type.arity());
+    assert_eq!(Ok(()), set.validate());
+}
+
+#[test]
+/// A single set type must not be wrapped in a concatenation.
+fn test_set_type_single_concatenation() {
+    let set = Set {
+        set_type: SetTypeValue::Concatenated(vec![SetType::Ipv4Addr]),
+        ..Set::default()
+    };
+    assert_eq!(
+        Err(ValidationError::InvalidConcatenation(1)),
+        set.validate()
+    );
+}
+
+#[test]
+/// A base chain must have a policy.
+fn test_chain_base_missing_policy() {
+    let mut chain = Chain {
+        _type: Some(NfChainType::Filter),
+        hook: Some(NfHook::Input),
+        prio: Some(0),
+        ..Chain::default()
+    };
+    assert_eq!(
+        Err(ValidationError::MissingChainPolicy("forward".to_string())),
+        chain.validate()
+    );
+    chain.policy = Some(NfChainPolicy::Drop);
+    assert_eq!(Ok(()), chain.validate());
+}
+
+#[test]
+/// A regular chain does not need a policy.
+fn test_chain_regular_without_policy() {
+    assert_eq!(Ok(()), Chain::default().validate());
+}
+
+#[test]
+fn test_payload_field_known() {
+    let payload = PayloadField {
+        protocol: "tcp".into(),
+        field: "dport".to_string(),
+    };
+    assert_eq!(Ok(()), payload.validate());
+}
+
+#[test]
+fn test_payload_field_unknown() {
+    let payload = PayloadField {
+        protocol: "tcp".into(),
+        field: "nosuchfield".to_string(),
+    };
+    assert_eq!(
+        Err(ValidationError::UnknownPayloadField {
+            protocol: "tcp".into(),
+            field: "nosuchfield".to_string(),
+        }),
+        payload.validate()
+    );
+    let payload = PayloadField {
+        protocol: "tpc".into(),
+        field: "dport".to_string(),
+    };
+    assert_eq!(
+        Err(ValidationError::UnknownPayloadProtocol("tpc".to_string())),
+        payload.validate()
+    );
+}
+
+#[test]
+fn test_tcp_option_known() {
+    assert_eq!(Ok(()), TcpOption::new("maxseg", "size").validate());
+    assert_eq!(Ok(()), TcpOption::new("window", "count").validate());
+}
+
+#[test]
+fn test_tcp_option_unknown() {
+    assert_eq!(
+        Err(ValidationError::UnknownTcpOptionField {
+            option: "maxseg".to_string(),
+            field: "count".to_string(),
+        }),
+        TcpOption::new("maxseg", "count").validate()
+    );
+    assert_eq!(
+        Err(ValidationError::UnknownTcpOption("mss".to_string())),
+        TcpOption::new("mss", "size").validate()
+    );
+}
+
+#[test]
+/// Logging to syslog with a level is valid.
+fn test_log_syslog() {
+    let log = Log {
+        prefix: Some("dropped: ".to_string()),
+        level: Some(LogLevel::Info),
+        ..Log::new(None)
+    };
+    assert_eq!(Ok(()), log.validate());
+}
+
+#[test]
+/// Log level and nflog group cannot be combined.
+fn test_log_group_and_level() {
+    let log = Log {
+        level: Some(LogLevel::Info),
+        ..Log::new(Some(2))
+    };
+    assert_eq!(
+        Err(ValidationError::ConflictingLogOptions("level", "group")),
+        log.validate()
+    );
+}
+
+fn dnat_rule(family: NfFamily, nat_family: Option<NATFamily>) -> Rule {
+    Rule::new(
+        family,
+        "nat",
+        "prerouting",
+        [Statement::DNAT(Some(NAT {
+            addr: Some(Expression::String("10.0.0.1".to_string())),
+            family: nat_family,
+            port: Some(8080),
+            flags: None,
+        }))],
+    )
+}
+
+#[test]
+/// NAT statements in inet tables have to state the address family.
+fn test_nat_inet_without_family() {
+    assert_eq!(
+        Err(ValidationError::MissingNatFamily),
+        dnat_rule(NfFamily::INet, None).validate()
+    );
+    assert_eq!(
+        Ok(()),
+        dnat_rule(NfFamily::INet, Some(NATFamily::IP)).validate()
+    );
+}
+
+#[test]
+/// NAT statements in ip tables can omit the address family.
+fn test_nat_ip_without_family() {
+    assert_eq!(Ok(()), dnat_rule(NfFamily::IP, None).validate());
+}
+
+#[test]
+/// Ranges can only be added to interval sets.
+fn test_element_range_interval_set() {
+    let element = Element {
+        elem: vec![Expression::from(1024..=2048)],
+        ..Element::default()
+    };
+    let mut set = Set {
+        set_type: SetTypeValue::Single(SetType::InetService),
+        ..Set::default()
+    };
+    assert_eq!(
+        Err(ValidationError::IntervalInPlainSet("myset".to_string())),
+        element.validate_against(&set)
+    );
+    set.flags = Some(HashSet::from([SetFlag::Interval]));
+    assert_eq!(Ok(()), element.validate_against(&set));
+}
+
+#[test]
+/// Elements of concatenated sets need one component per set type component.
+fn test_element_concatenation_arity() {
+    let set = Set {
+        set_type: SetTypeValue::Concatenated(vec![SetType::Ipv4Addr, SetType::InetService]),
+        ..Set::default()
+    };
+    let element = Element {
+        elem: vec![Expression::concat([
+            Expression::String("10.0.0.1".to_string()),
+            Expression::Number(22),
+        ])],
+        ..Element::default()
+    };
+    assert_eq!(Ok(()), element.validate_against(&set));
+    let element = Element {
+        elem: vec![Expression::String("10.0.0.1".to_string())],
+        ..Element::default()
+    };
+    assert_eq!(
+        Err(ValidationError::ArityMismatch {
+            set: "myset".to_string(),
+            expected: 2,
+            found: 1
+        }),
+        element.validate_against(&set)
+    );
+}
+
+#[test]
+/// Only interval sets can merge elements automatically.
+fn test_set_auto_merge_interval() {
+    let mut set = Set {
+        auto_merge: Some(true),
+        ..Set::default()
+    };
+    assert_eq!(
+        Err(ValidationError::AutoMergeWithoutInterval(
+            "myset".to_string()
+        )),
+        set.validate()
+    );
+    set.flags = Some(HashSet::from([SetFlag::Interval]));
+    assert_eq!(Ok(()), set.validate());
+}
+
+#[test]
+/// Constant queue numbers, including range bounds, have to fit in 16 bits.
+fn test_queue_number_range() {
+    assert_eq!(Ok(()), Queue::to(u16::MAX).validate());
+    let queue = Queue {
+        num: Expression::from(0..=65536),
+        flags: None,
+    };
+    assert_eq!(
+        Err(ValidationError::QueueNumberOutOfRange(65536)),
+        Statement::Queue(queue).validate(NfFamily::INet)
+    );
+}
+
+#[test]
+/// A burst unit only applies to limits in bytes.
+fn test_limit_burst_unit_packets() {
+    let mut limit = Limit {
+        rate: 10,
+        rate_unit: None,
+        per: Some("second".to_string()),
+        burst: Some(5),
+        burst_unit: Some("bytes".to_string()),
+        inv: None,
+    };
+    assert_eq!(
+        Err(ValidationError::BurstUnitInPacketLimit),
+        Statement::Limit(limit.clone()).validate(NfFamily::INet)
+    );
+    limit.rate_unit = Some("bytes".to_string());
+    assert_eq!(Ok(()), limit.validate());
+}
+
+#[test]
+/// Comments are limited to 128 bytes without quotes or line breaks.
+fn test_rule_comment() {
+    let mut rule = Rule::new(NfFamily::IP, "filter", "input", [Statement::Accept(None)]);
+    rule.comment = Some("allow established connections".to_string());
+    assert_eq!(Ok(()), rule.validate());
+
+    rule.comment = Some("x".repeat(129));
+    assert_eq!(Err(ValidationError::CommentTooLong(129)), rule.validate());
+
+    rule.comment = Some("say \"hi\"".to_string());
+    assert_eq!(
+        Err(ValidationError::InvalidCommentCharacter('"')),
+        rule.validate()
+    );
+
+    let set = Set {
+        comment: Some("first line\nsecond line".to_string()),
+        ..Set::default()
+    };
+    assert_eq!(
+        Err(ValidationError::InvalidCommentCharacter('\n')),
+        set.validate()
+    );
+}
+
+#[test]
+/// A named synproxy needs both mss and wscale, or neither.
+fn test_synproxy_mss_wscale() {
+    let mut synproxy = SynProxy::new(NfFamily::IP, "filter", "backend", 1460, 7);
+    assert_eq!(Ok(()), synproxy.validate());
+
+    synproxy.wscale = None;
+    assert_eq!(
+        Err(ValidationError::IncompleteSynProxy("backend".to_string())),
+        synproxy.validate()
+    );
+
+    synproxy.mss = None;
+    assert_eq!(Ok(()), synproxy.validate());
+}